@@ -1,20 +1,28 @@
 use std::{
-    env,
+    env, fs,
     path::{Path, PathBuf},
     process::{Command, ExitStatus},
 };
 
-use cargo_metadata::{Metadata as CargoMetadata, MetadataCommand, Target as CargoTarget};
+use cargo_metadata::{
+    Metadata as CargoMetadata, MetadataCommand, Package as CargoPackage, Target as CargoTarget,
+};
+
+use crate::{
+    config::CustomAction,
+    util::{join_words, split_words},
+    Action, ExecOptions, FeatureSelection, Target, TargetKind, Workspace,
+};
 
-use crate::{Action, Target, TargetKind};
+pub const DEV_PROFILE: &str = "dev";
+pub const RELEASE_PROFILE: &str = "release";
 
 fn convert(metadata: CargoMetadata, current_dir: &Path) -> Vec<Target> {
     let mut targets = Vec::new();
     for p in &metadata.packages {
-        let package_name = p.name.as_str();
         for t in &p.targets {
             if is_select_target(t) {
-                targets.push(build_target(t, package_name, current_dir));
+                targets.push(build_target(t, p, current_dir));
             }
         }
     }
@@ -22,30 +30,57 @@ fn convert(metadata: CargoMetadata, current_dir: &Path) -> Vec<Target> {
 }
 
 fn is_select_target(t: &CargoTarget) -> bool {
-    t.is_bin() || t.is_example()
+    t.is_bin() || t.is_example() || t.is_test() || t.is_bench() || is_lib_target(t)
 }
 
-fn build_target(t: &CargoTarget, package_name: &str, current_dir: &Path) -> Target {
+fn is_lib_target(t: &CargoTarget) -> bool {
+    t.is_lib()
+        || t.is_rlib()
+        || t.is_dylib()
+        || t.is_cdylib()
+        || t.is_staticlib()
+        || t.is_proc_macro()
+}
+
+fn build_target(t: &CargoTarget, p: &CargoPackage, current_dir: &Path) -> Target {
     let name = t.name.to_owned();
-    let package = package_name.to_owned();
+    let package = p.name.to_string();
+    let package_dir = p
+        .manifest_path
+        .parent()
+        .map(|p| p.to_string())
+        .unwrap_or_default();
     let kind = if t.is_bin() {
         TargetKind::Bin
-    } else {
+    } else if t.is_example() {
         TargetKind::Example
+    } else if t.is_test() {
+        TargetKind::Test
+    } else if t.is_bench() {
+        TargetKind::Bench
+    } else {
+        TargetKind::Lib
     };
     let path = t
         .src_path
         .strip_prefix(current_dir)
         .map(|p| p.to_string())
         .unwrap_or("-".to_string());
+    let src_path = t.src_path.to_string();
     let required_features = t.required_features.clone();
+    let package_features = p.features.clone();
+    let package_description = p.description.clone();
 
     Target {
         name,
         package,
+        package_dir,
         kind,
         path,
+        src_path,
         required_features,
+        package_features,
+        package_description,
     }
 }
 
@@ -53,71 +88,341 @@ fn get_current_dir() -> PathBuf {
     env::current_dir().expect("failed to get current directory")
 }
 
-pub fn get_all_targets() -> Vec<Target> {
+pub fn get_workspace() -> Workspace {
     let metadata = MetadataCommand::new()
         .no_deps()
         .exec()
         .expect("failed to exec metadata command");
     let current_dir = get_current_dir();
-    convert(metadata, &current_dir)
+    let root = metadata.workspace_root.to_string();
+    let profiles = fs::read_to_string(metadata.workspace_root.join("Cargo.toml"))
+        .map(|manifest| parse_profiles(&manifest))
+        .unwrap_or_else(|_| parse_profiles(""));
+    let targets = convert(metadata, &current_dir);
+    Workspace {
+        root,
+        targets,
+        profiles,
+    }
+}
+
+// `dev` and `release` followed by the custom profiles declared in the manifest
+fn parse_profiles(manifest: &str) -> Vec<String> {
+    let mut profiles = vec![DEV_PROFILE.to_string(), RELEASE_PROFILE.to_string()];
+    let table: toml::Table = manifest.parse().unwrap_or_default();
+    if let Some(toml::Value::Table(ps)) = table.get("profile") {
+        for name in ps.keys() {
+            if !profiles.contains(name) {
+                profiles.push(name.clone());
+            }
+        }
+    }
+    profiles
+}
+
+pub fn can_list_tests(target: &Target) -> bool {
+    matches!(
+        target.kind,
+        TargetKind::Bin | TargetKind::Test | TargetKind::Lib
+    )
+}
+
+pub fn list_tests(target: &Target, options: &ExecOptions) -> Result<Vec<String>, String> {
+    let mut cmd = Command::new("cargo");
+    cmd.arg("test");
+    append_target_args(&mut cmd, target, &options.features);
+    append_profile_args(&mut cmd, "test", &options.profile);
+    cmd.arg("--").arg("--list").arg("--format").arg("terse");
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("failed to spawn cargo test command: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let msg = stderr
+            .lines()
+            .rev()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or("failed to list tests");
+        return Err(msg.trim().to_string());
+    }
+    Ok(parse_test_list(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_test_list(s: &str) -> Vec<String> {
+    s.lines()
+        .filter_map(|l| l.strip_suffix(": test"))
+        .map(|l| l.to_string())
+        .collect()
 }
 
 pub fn exec_cargo_run(
     target: &Target,
     action: &Action,
-    additional_args: Option<String>,
-) -> ExitStatus {
-    let action = match action {
-        Action::Run => "run",
+    options: &ExecOptions,
+    additional_args: &[String],
+) -> Result<ExitStatus, String> {
+    exec(build_command(target, action, options, additional_args))
+}
+
+// the command line `exec_cargo_run` would execute
+pub fn command_line(
+    target: &Target,
+    action: &Action,
+    options: &ExecOptions,
+    additional_args: &[String],
+) -> String {
+    cmd_str(&build_command(target, action, options, additional_args))
+}
+
+fn build_command(
+    target: &Target,
+    action: &Action,
+    options: &ExecOptions,
+    additional_args: &[String],
+) -> Command {
+    if let Action::Custom(c) = action {
+        return build_custom_command(target, c, options, additional_args);
+    }
+
+    let subcommand = subcommand(target, action);
+    let mut cmd = Command::new("cargo");
+    cmd.arg(subcommand);
+    if let Action::Install = action {
+        cmd.arg("--path").arg(&target.package_dir);
+        append_kind_args(&mut cmd, target);
+        append_features_args(&mut cmd, target, &options.features);
+    } else {
+        append_target_args(&mut cmd, target, &options.features);
+    }
+    append_profile_args(&mut cmd, subcommand, &options.profile);
+
+    let mut trailing_args: Vec<&str> = Vec::new();
+    if action.takes_args() {
+        trailing_args.extend(options.args.iter().map(|a| a.as_str()));
+    }
+    if let Action::Asm = action {
+        trailing_args.extend(["--emit", "asm"]);
+    }
+    append_additional_args(&mut cmd, additional_args, &trailing_args);
+
+    cmd
+}
+
+fn subcommand(target: &Target, action: &Action) -> &'static str {
+    match action {
+        Action::Run => match target.kind {
+            TargetKind::Bin | TargetKind::Example => "run",
+            TargetKind::Test | TargetKind::Lib => "test",
+            TargetKind::Bench => "bench",
+        },
         Action::Build => "build",
+        Action::Check => "check",
+        Action::Clippy => "clippy",
+        Action::Doc => "doc",
+        Action::Test => "test",
+        Action::Bench => "bench",
+        Action::Install => "install",
+        Action::Asm => "rustc",
+        Action::Custom(_) => unreachable!(),
+    }
+}
+
+const NAME_PLACEHOLDER: &str = "{name}";
+const KIND_FLAG_PLACEHOLDER: &str = "{kind_flag}";
+
+fn build_custom_command(
+    target: &Target,
+    action: &CustomAction,
+    options: &ExecOptions,
+    additional_args: &[String],
+) -> Command {
+    let words = split_words(&action.command)
+        .unwrap_or_else(|e| panic!("invalid command of action `{}`: {e}", action.name));
+    let mut rendered = Vec::with_capacity(words.len());
+    let mut words = words.into_iter().peekable();
+    while let Some(w) = words.next() {
+        // `--lib` takes no name
+        if target.kind == TargetKind::Lib
+            && w == KIND_FLAG_PLACEHOLDER
+            && words.peek().is_some_and(|n| n == NAME_PLACEHOLDER)
+        {
+            words.next();
+        }
+        // a placeholder rendered empty is kept as an empty argument, like a quoted `""`
+        rendered.push(render_template(&w, target, options));
+    }
+    let mut words = rendered.into_iter();
+
+    let program = words
+        .next()
+        .unwrap_or_else(|| panic!("empty command of action `{}`", action.name));
+    let mut cmd = Command::new(program);
+    cmd.args(words);
+    let trailing_args: Vec<&str> = options.args.iter().map(|a| a.as_str()).collect();
+    append_additional_args(&mut cmd, additional_args, &trailing_args);
+
+    cmd
+}
+
+fn render_template(template: &str, target: &Target, options: &ExecOptions) -> String {
+    let template = if target.kind == TargetKind::Lib {
+        let pair = format!("{KIND_FLAG_PLACEHOLDER} {NAME_PLACEHOLDER}");
+        template.replace(&pair, KIND_FLAG_PLACEHOLDER)
+    } else {
+        template.to_string()
     };
-    let kind = match target.kind {
-        TargetKind::Bin => "--bin",
-        TargetKind::Example => "--example",
-    };
-    let name = &target.name;
+    template
+        .replace(NAME_PLACEHOLDER, &target.name)
+        .replace("{package}", &target.package)
+        .replace(KIND_FLAG_PLACEHOLDER, kind_flag(target.kind))
+        .replace("{path}", &target.src_path)
+        .replace(
+            "{features}",
+            &all_features(target, &options.features).join(" "),
+        )
+        .replace("{profile}", &options.profile)
+}
+
+pub fn exec_cargo_test(
+    target: &Target,
+    test_name: &str,
+    options: &ExecOptions,
+    additional_args: &[String],
+) -> Result<ExitStatus, String> {
+    exec(build_test_command(
+        target,
+        test_name,
+        options,
+        additional_args,
+    ))
+}
 
+// the command line `exec_cargo_test` would execute
+pub fn test_command_line(
+    target: &Target,
+    test_name: &str,
+    options: &ExecOptions,
+    additional_args: &[String],
+) -> String {
+    cmd_str(&build_test_command(
+        target,
+        test_name,
+        options,
+        additional_args,
+    ))
+}
+
+fn build_test_command(
+    target: &Target,
+    test_name: &str,
+    options: &ExecOptions,
+    additional_args: &[String],
+) -> Command {
     let mut cmd = Command::new("cargo");
-    cmd.arg(action).arg(kind).arg(name);
+    cmd.arg("test");
+    append_target_args(&mut cmd, target, &options.features);
+    append_profile_args(&mut cmd, "test", &options.profile);
+    append_additional_args(&mut cmd, additional_args, &[test_name, "--exact"]);
+    cmd
+}
 
+fn append_target_args(cmd: &mut Command, target: &Target, features: &FeatureSelection) {
+    append_kind_args(cmd, target);
     cmd.arg("--package").arg(&target.package);
+    append_features_args(cmd, target, features);
+}
+
+fn append_kind_args(cmd: &mut Command, target: &Target) {
+    cmd.arg(kind_flag(target.kind));
+    if target.kind != TargetKind::Lib {
+        cmd.arg(&target.name);
+    }
+}
 
-    let require_features = !target.required_features.is_empty();
+fn kind_flag(kind: TargetKind) -> &'static str {
+    match kind {
+        TargetKind::Bin => "--bin",
+        TargetKind::Example => "--example",
+        TargetKind::Test => "--test",
+        TargetKind::Bench => "--bench",
+        TargetKind::Lib => "--lib",
+    }
+}
 
-    if require_features {
-        let features = target.required_features.join(" ");
-        cmd.arg("--features").arg(&features);
+fn append_features_args(cmd: &mut Command, target: &Target, features: &FeatureSelection) {
+    let all = all_features(target, features);
+
+    if !all.is_empty() {
+        cmd.arg("--features").arg(all.join(" "));
     };
+    if features.no_default_features {
+        cmd.arg("--no-default-features");
+    }
+    if features.all_features {
+        cmd.arg("--all-features");
+    }
+}
 
-    if let Some(args) = additional_args {
-        // todo: handle quoted arguments properly
-        args.split_whitespace().for_each(|a| {
-            cmd.arg(a);
-        });
+// `dev` keeps the default profile of each command except `cargo install`, which builds with
+// `release` by default, and `cargo bench` has no `--release` flag
+fn append_profile_args(cmd: &mut Command, subcommand: &str, profile: &str) {
+    match profile {
+        DEV_PROFILE if subcommand == "install" => {
+            cmd.arg("--debug");
+        }
+        DEV_PROFILE => {}
+        RELEASE_PROFILE if subcommand == "install" => {}
+        RELEASE_PROFILE if subcommand != "bench" => {
+            cmd.arg("--release");
+        }
+        _ => {
+            cmd.arg("--profile").arg(profile);
+        }
+    }
+}
+
+// required features of the target followed by the selected features
+pub fn all_features<'a>(target: &'a Target, features: &'a FeatureSelection) -> Vec<&'a str> {
+    let mut all: Vec<&str> = target
+        .required_features
+        .iter()
+        .map(|f| f.as_str())
+        .collect();
+    for f in &features.features {
+        if !all.contains(&f.as_str()) {
+            all.push(f);
+        }
+    }
+    all
+}
+
+// `trailing_args` are passed after `--`, following any arguments the user has already put there
+fn append_additional_args(cmd: &mut Command, additional_args: &[String], trailing_args: &[&str]) {
+    cmd.args(additional_args);
+    if !trailing_args.is_empty() {
+        if !additional_args.iter().any(|a| a == "--") {
+            cmd.arg("--");
+        }
+        cmd.args(trailing_args);
     }
+}
 
+fn exec(mut cmd: Command) -> Result<ExitStatus, String> {
     eprintln!("{}", cmd_str(&cmd));
 
+    let program = cmd.get_program().to_string_lossy().into_owned();
     cmd.spawn()
-        .unwrap_or_else(|_| panic!("failed to spawn cargo {action} command"))
-        .wait()
-        .unwrap()
+        .and_then(|mut child| child.wait())
+        .map_err(|e| format!("failed to spawn {program}: {e}"))
 }
 
 fn cmd_str(cmd: &Command) -> String {
-    let program = cmd.get_program().to_string_lossy();
-    let args = cmd
-        .get_args()
-        .map(|a| {
-            let a = a.to_string_lossy();
-            if a.contains(char::is_whitespace) {
-                format!("\"{a}\"").into()
-            } else {
-                a
-            }
-        })
-        .collect::<Vec<_>>();
-    format!("{} {}", program, args.join(" "))
+    let words: Vec<_> = std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|a| a.to_string_lossy())
+        .collect();
+    join_words(&words)
 }
 
 #[cfg(test)]
@@ -139,6 +444,10 @@ mod tests {
         vec!["cargo", "run", "--bin", "xyz", "--", "-p", "Hello World", "-n", "1"],
         "cargo run --bin xyz -- -p \"Hello World\" -n 1",
     )]
+    #[case(
+        vec!["cargo", "run", "--bin", "xyz", "--", "--msg", "say \"hi\" to $USER", ""],
+        "cargo run --bin xyz -- --msg 'say \"hi\" to $USER' \"\"",
+    )]
     fn test_cmd_str(#[case] args: Vec<&str>, #[case] expected: &str) {
         let mut cmd = Command::new(args[0]);
         args.iter().skip(1).for_each(|a| {
@@ -146,4 +455,323 @@ mod tests {
         });
         assert_eq!(cmd_str(&cmd), expected);
     }
+
+    fn target(kind: TargetKind, required_features: Vec<&str>) -> Target {
+        Target {
+            required_features: required_features.into_iter().map(Into::into).collect(),
+            ..Target::fixture("xyz", kind, "src/main.rs")
+        }
+    }
+
+    fn custom(command: &str) -> Action {
+        Action::Custom(CustomAction {
+            name: "custom".into(),
+            command: command.into(),
+            bg: ratatui::style::Color::White,
+            fg: ratatui::style::Color::Black,
+            kinds: vec![],
+        })
+    }
+
+    #[rstest]
+    #[case(
+        target(TargetKind::Bin, vec![]),
+        Action::Run,
+        vec![],
+        "cargo run --bin xyz --package pkg",
+    )]
+    #[case(
+        target(TargetKind::Test, vec![]),
+        Action::Run,
+        vec![],
+        "cargo test --test xyz --package pkg",
+    )]
+    #[case(
+        target(TargetKind::Lib, vec![]),
+        Action::Clippy,
+        vec![],
+        "cargo clippy --lib --package pkg",
+    )]
+    #[case(
+        target(TargetKind::Example, vec!["foo", "bar"]),
+        Action::Check,
+        vec![],
+        "cargo check --example xyz --package pkg --features \"foo bar\"",
+    )]
+    #[case(
+        target(TargetKind::Bin, vec![]),
+        Action::Install,
+        vec![],
+        "cargo install --path /path/to/pkg --bin xyz --debug",
+    )]
+    #[case(
+        target(TargetKind::Bin, vec![]),
+        Action::Asm,
+        vec![],
+        "cargo rustc --bin xyz --package pkg -- --emit asm",
+    )]
+    #[case(
+        target(TargetKind::Bin, vec![]),
+        Action::Asm,
+        vec!["--release", "--", "-C", "opt-level=3"],
+        "cargo rustc --bin xyz --package pkg --release -- -C opt-level=3 --emit asm",
+    )]
+    #[case(
+        target(TargetKind::Bin, vec!["foo"]),
+        custom("cargo flamegraph {kind_flag} {name} --package {package} --features {features}"),
+        vec![],
+        "cargo flamegraph --bin xyz --package pkg --features foo",
+    )]
+    #[case(
+        target(TargetKind::Example, vec!["foo", "bar"]),
+        custom("cargo watch -x \"run {kind_flag} {name} --features '{features}'\""),
+        vec!["-c"],
+        "cargo watch -x \"run --example xyz --features 'foo bar'\" -c",
+    )]
+    #[case(
+        target(TargetKind::Bin, vec![]),
+        custom("hyperfine target/release/{name}"),
+        vec![],
+        "hyperfine target/release/xyz",
+    )]
+    #[case(
+        target(TargetKind::Bin, vec![]),
+        custom("cargo flamegraph --features {features} --bin {name}"),
+        vec![],
+        "cargo flamegraph --features \"\" --bin xyz",
+    )]
+    #[case(
+        target(TargetKind::Bin, vec![]),
+        custom("echo \"\" {name}"),
+        vec![],
+        "echo \"\" xyz",
+    )]
+    #[case(
+        target(TargetKind::Lib, vec![]),
+        custom("cargo flamegraph {kind_flag} {name} --package {package}"),
+        vec![],
+        "cargo flamegraph --lib --package pkg",
+    )]
+    #[case(
+        target(TargetKind::Lib, vec![]),
+        custom("cargo watch -x \"clippy {kind_flag} {name}\" -s {name}"),
+        vec![],
+        "cargo watch -x \"clippy --lib\" -s xyz",
+    )]
+    #[case(
+        target(TargetKind::Bin, vec![]),
+        custom("wc -l {path}"),
+        vec![],
+        "wc -l /path/to/pkg/src/main.rs",
+    )]
+    fn test_build_command(
+        #[case] target: Target,
+        #[case] action: Action,
+        #[case] additional_args: Vec<&str>,
+        #[case] expected: &str,
+    ) {
+        let options = ExecOptions::default();
+        let additional_args: Vec<String> = additional_args.into_iter().map(Into::into).collect();
+        let cmd = build_command(&target, &action, &options, &additional_args);
+        assert_eq!(cmd_str(&cmd), expected);
+    }
+
+    #[rstest]
+    #[case(
+        vec![],
+        vec!["foo"],
+        false,
+        false,
+        "cargo run --bin xyz --package pkg --features foo",
+    )]
+    #[case(
+        vec!["foo"],
+        vec!["bar", "foo"],
+        true,
+        false,
+        "cargo run --bin xyz --package pkg --features \"foo bar\" --no-default-features",
+    )]
+    #[case(
+        vec![],
+        vec![],
+        false,
+        true,
+        "cargo run --bin xyz --package pkg --all-features",
+    )]
+    fn test_build_command_with_features(
+        #[case] required_features: Vec<&str>,
+        #[case] selected_features: Vec<&str>,
+        #[case] no_default_features: bool,
+        #[case] all_features: bool,
+        #[case] expected: &str,
+    ) {
+        let target = target(TargetKind::Bin, required_features);
+        let options = ExecOptions {
+            features: FeatureSelection {
+                features: selected_features.into_iter().map(Into::into).collect(),
+                no_default_features,
+                all_features,
+            },
+            ..Default::default()
+        };
+        let cmd = build_command(&target, &Action::Run, &options, &[]);
+        assert_eq!(cmd_str(&cmd), expected);
+    }
+
+    #[rstest]
+    #[case(
+        TargetKind::Bin,
+        Action::Run,
+        "dev",
+        "cargo run --bin xyz --package pkg"
+    )]
+    #[case(
+        TargetKind::Bin,
+        Action::Run,
+        "release",
+        "cargo run --bin xyz --package pkg --release"
+    )]
+    #[case(
+        TargetKind::Test,
+        Action::Run,
+        "fast",
+        "cargo test --test xyz --package pkg --profile fast"
+    )]
+    #[case(
+        TargetKind::Bench,
+        Action::Run,
+        "release",
+        "cargo bench --bench xyz --package pkg --profile release"
+    )]
+    #[case(
+        TargetKind::Bin,
+        Action::Install,
+        "dev",
+        "cargo install --path /path/to/pkg --bin xyz --debug"
+    )]
+    #[case(
+        TargetKind::Bin,
+        Action::Install,
+        "release",
+        "cargo install --path /path/to/pkg --bin xyz"
+    )]
+    #[case(
+        TargetKind::Bin,
+        Action::Install,
+        "fast",
+        "cargo install --path /path/to/pkg --bin xyz --profile fast"
+    )]
+    #[case(
+        TargetKind::Bin,
+        custom("cargo flamegraph --bin {name} --profile {profile}"),
+        "fast",
+        "cargo flamegraph --bin xyz --profile fast"
+    )]
+    fn test_build_command_with_profile(
+        #[case] kind: TargetKind,
+        #[case] action: Action,
+        #[case] profile: &str,
+        #[case] expected: &str,
+    ) {
+        let target = target(kind, vec![]);
+        let options = ExecOptions {
+            profile: profile.into(),
+            ..Default::default()
+        };
+        let cmd = build_command(&target, &action, &options, &[]);
+        assert_eq!(cmd_str(&cmd), expected);
+    }
+
+    #[rstest]
+    #[case(Action::Run, vec![], vec![], "cargo run --bin xyz --package pkg")]
+    #[case(
+        Action::Run,
+        vec![],
+        vec!["--name", "Hello World"],
+        "cargo run --bin xyz --package pkg -- --name \"Hello World\"",
+    )]
+    #[case(
+        Action::Run,
+        vec!["--release", "--", "-v"],
+        vec!["--name", "Hello World"],
+        "cargo run --bin xyz --package pkg --release -- -v --name \"Hello World\"",
+    )]
+    #[case(
+        Action::Test,
+        vec![],
+        vec!["--nocapture"],
+        "cargo test --bin xyz --package pkg -- --nocapture",
+    )]
+    #[case(
+        Action::Build,
+        vec![],
+        vec!["--name", "Hello World"],
+        "cargo build --bin xyz --package pkg",
+    )]
+    #[case(
+        Action::Build,
+        vec!["--", "-v"],
+        vec!["--name", "Hello World"],
+        "cargo build --bin xyz --package pkg -- -v",
+    )]
+    #[case(
+        Action::Install,
+        vec![],
+        vec!["x"],
+        "cargo install --path /path/to/pkg --bin xyz --debug",
+    )]
+    #[case(
+        Action::Asm,
+        vec![],
+        vec!["-C", "opt-level=3"],
+        "cargo rustc --bin xyz --package pkg -- --emit asm",
+    )]
+    #[case(
+        custom("cargo flamegraph --bin {name}"),
+        vec![],
+        vec!["--name", "Hello World"],
+        "cargo flamegraph --bin xyz -- --name \"Hello World\"",
+    )]
+    fn test_build_command_with_args(
+        #[case] action: Action,
+        #[case] additional_args: Vec<&str>,
+        #[case] args: Vec<&str>,
+        #[case] expected: &str,
+    ) {
+        let target = target(TargetKind::Bin, vec![]);
+        let options = ExecOptions {
+            args: args.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        };
+        let additional_args: Vec<String> = additional_args.into_iter().map(Into::into).collect();
+        let cmd = build_command(&target, &action, &options, &additional_args);
+        assert_eq!(cmd_str(&cmd), expected);
+    }
+
+    #[test]
+    fn test_exec_spawn_failure() {
+        let cmd = Command::new("cargo-selector-no-such-program");
+        let err = exec(cmd).unwrap_err();
+        assert!(
+            err.starts_with("failed to spawn cargo-selector-no-such-program: "),
+            "{err}"
+        );
+    }
+
+    #[rstest]
+    #[case("", vec!["dev", "release"])]
+    #[case(
+        "[package]\nname = \"xyz\"\n\n[profile.release]\nlto = true\n\n[profile.bench-fast]\ninherits = \"release\"\n",
+        vec!["dev", "release", "bench-fast"],
+    )]
+    #[case("invalid toml", vec!["dev", "release"])]
+    fn test_parse_profiles(#[case] manifest: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(parse_profiles(manifest), expected);
+    }
+
+    #[test]
+    fn test_parse_test_list() {
+        let s = "tests::foo: test\ntests::bar::baz: test\nbench_qux: benchmark\n\n";
+        assert_eq!(parse_test_list(s), vec!["tests::foo", "tests::bar::baz"]);
+    }
 }
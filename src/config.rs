@@ -4,7 +4,12 @@ use ratatui::style::Color;
 use serde::Deserialize;
 use umbra::optional;
 
-use crate::MatchType;
+use crate::{
+    layout::{Align, Column, ColumnWidth, Truncate},
+    preview::PreviewPosition,
+    util::split_words,
+    Case, MatchType, TargetKind,
+};
 
 const CONFIG_PATH_ENV_VAR: &str = "CARGO_SELECTOR_CONFIG";
 
@@ -12,6 +17,11 @@ const CONFIG_PATH_ENV_VAR: &str = "CARGO_SELECTOR_CONFIG";
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct Config {
     pub match_type: Option<MatchType>,
+    pub case: Option<Case>,
+    pub actions: Vec<CustomAction>,
+    pub columns: ColumnsConfig,
+    pub group_by_package: bool,
+    pub preview: PreviewConfig,
     #[nested]
     pub color: ColorTheme,
 }
@@ -21,13 +31,104 @@ impl Config {
         if let Ok(path) = env::var(CONFIG_PATH_ENV_VAR) {
             let content = std::fs::read_to_string(path).unwrap();
             let config: OptionalConfig = toml::from_str(&content).unwrap();
-            config.into()
+            let config: Config = config.into();
+            for action in &config.actions {
+                if let Err(e) = split_words(&action.command) {
+                    panic!("invalid command of action `{}`: {e}", action.name);
+                }
+            }
+            config
         } else {
             Config::default()
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CustomAction {
+    pub name: String,
+    pub command: String,
+    #[serde(default = "default_custom_action_bg")]
+    pub bg: Color,
+    #[serde(default = "default_custom_action_fg")]
+    pub fg: Color,
+    #[serde(default)]
+    pub kinds: Vec<TargetKind>,
+}
+
+fn default_custom_action_bg() -> Color {
+    Color::White
+}
+
+fn default_custom_action_fg() -> Color {
+    Color::Black
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ColumnsConfig {
+    // the columns shown in the list, in this order
+    pub show: Vec<Column>,
+    pub kind: ColumnOptions,
+    pub name: ColumnOptions,
+    pub package: ColumnOptions,
+    pub path: ColumnOptions,
+    pub features: ColumnOptions,
+    pub description: ColumnOptions,
+    pub last_run: ColumnOptions,
+}
+
+impl Default for ColumnsConfig {
+    fn default() -> Self {
+        Self {
+            show: vec![
+                Column::Kind,
+                Column::Name,
+                Column::Package,
+                Column::Path,
+                Column::Features,
+            ],
+            kind: ColumnOptions::default(),
+            name: ColumnOptions::default(),
+            package: ColumnOptions::default(),
+            path: ColumnOptions::default(),
+            features: ColumnOptions::default(),
+            description: ColumnOptions::default(),
+            last_run: ColumnOptions::default(),
+        }
+    }
+}
+
+impl ColumnsConfig {
+    pub fn options(&self, column: Column) -> &ColumnOptions {
+        match column {
+            Column::Kind => &self.kind,
+            Column::Name => &self.name,
+            Column::Package => &self.package,
+            Column::Path => &self.path,
+            Column::Features => &self.features,
+            Column::Description => &self.description,
+            Column::LastRun => &self.last_run,
+        }
+    }
+}
+
+// unset options use the defaults of the column
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+pub struct ColumnOptions {
+    pub width: Option<ColumnWidth>,
+    pub align: Option<Align>,
+    pub truncate: Option<Truncate>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct PreviewConfig {
+    // shown on startup, and toggled with a key
+    pub show: bool,
+    pub position: PreviewPosition,
+}
+
 #[optional(derives = [Deserialize])]
 #[derive(Debug, PartialEq, Eq)]
 pub struct ColorTheme {
@@ -37,15 +138,40 @@ pub struct ColorTheme {
     pub action_run_fg: Color,
     pub action_build_bg: Color,
     pub action_build_fg: Color,
+    pub action_check_bg: Color,
+    pub action_check_fg: Color,
+    pub action_clippy_bg: Color,
+    pub action_clippy_fg: Color,
+    pub action_doc_bg: Color,
+    pub action_doc_fg: Color,
+    pub action_test_bg: Color,
+    pub action_test_fg: Color,
+    pub action_bench_bg: Color,
+    pub action_bench_fg: Color,
+    pub action_install_bg: Color,
+    pub action_install_fg: Color,
+    pub action_asm_bg: Color,
+    pub action_asm_fg: Color,
+
+    pub profile_bg: Color,
+    pub profile_fg: Color,
+    pub match_type_bg: Color,
+    pub match_type_fg: Color,
+    pub args_label_bg: Color,
+    pub args_label_fg: Color,
 
     pub input_fg: Color,
     pub numbers_fg: Color,
+    pub error_fg: Color,
 
     pub kind_fg: Color,
     pub name_fg: Color,
     pub name_match_fg: Color,
+    pub package_fg: Color,
     pub path_fg: Color,
     pub features_fg: Color,
+    pub description_fg: Color,
+    pub last_run_fg: Color,
 
     pub selected_bg: Color,
 }
@@ -59,15 +185,40 @@ impl Default for ColorTheme {
             action_run_fg: Color::Black,
             action_build_bg: Color::Blue,
             action_build_fg: Color::Black,
+            action_check_bg: Color::Cyan,
+            action_check_fg: Color::Black,
+            action_clippy_bg: Color::Yellow,
+            action_clippy_fg: Color::Black,
+            action_doc_bg: Color::Magenta,
+            action_doc_fg: Color::Black,
+            action_test_bg: Color::LightGreen,
+            action_test_fg: Color::Black,
+            action_bench_bg: Color::LightMagenta,
+            action_bench_fg: Color::Black,
+            action_install_bg: Color::LightBlue,
+            action_install_fg: Color::Black,
+            action_asm_bg: Color::LightRed,
+            action_asm_fg: Color::Black,
+
+            profile_bg: Color::Gray,
+            profile_fg: Color::Black,
+            match_type_bg: Color::DarkGray,
+            match_type_fg: Color::White,
+            args_label_bg: Color::DarkGray,
+            args_label_fg: Color::White,
 
             input_fg: Color::Reset,
             numbers_fg: Color::DarkGray,
+            error_fg: Color::Red,
 
             kind_fg: Color::Blue,
             name_fg: Color::White,
             name_match_fg: Color::Red,
+            package_fg: Color::Cyan,
             path_fg: Color::DarkGray,
             features_fg: Color::DarkGray,
+            description_fg: Color::DarkGray,
+            last_run_fg: Color::DarkGray,
 
             selected_bg: Color::Yellow,
         }
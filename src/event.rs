@@ -5,9 +5,19 @@ use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UserEvent {
     Quit,
+    Back,
     Down,
     Up,
-    ToggleAction,
+    NextAction,
+    PrevAction,
+    ListTests,
+    SelectFeatures,
+    EditArgs,
+    NextProfile,
+    NextMatchType,
+    GroupByPackage,
+    ToggleGroup,
+    TogglePreview,
     Execute,
 }
 
@@ -20,13 +30,23 @@ impl UserEventMapper {
     #[rustfmt::skip]
     pub fn new() -> UserEventMapper {
         let mut map = HashMap::new();
-        map.insert(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE), UserEvent::Quit);
+        map.insert(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE), UserEvent::Back);
         map.insert(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL), UserEvent::Quit);
         map.insert(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE), UserEvent::Down);
         map.insert(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL), UserEvent::Down);
         map.insert(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE), UserEvent::Up);
         map.insert(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL), UserEvent::Up);
-        map.insert(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE), UserEvent::ToggleAction);
+        map.insert(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE), UserEvent::NextAction);
+        map.insert(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT), UserEvent::PrevAction);
+        map.insert(KeyEvent::new(KeyCode::BackTab, KeyModifiers::NONE), UserEvent::PrevAction);
+        map.insert(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL), UserEvent::ListTests);
+        map.insert(KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL), UserEvent::SelectFeatures);
+        map.insert(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL), UserEvent::EditArgs);
+        map.insert(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL), UserEvent::NextProfile);
+        map.insert(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL), UserEvent::NextMatchType);
+        map.insert(KeyEvent::new(KeyCode::Char('g'), KeyModifiers::CONTROL), UserEvent::GroupByPackage);
+        map.insert(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::CONTROL), UserEvent::ToggleGroup);
+        map.insert(KeyEvent::new(KeyCode::Char('v'), KeyModifiers::CONTROL), UserEvent::TogglePreview);
         map.insert(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), UserEvent::Execute);
         UserEventMapper { map }
     }
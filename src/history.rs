@@ -0,0 +1,284 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    process::ExitStatus,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    state::{data_file_path, read_data_file, target_key},
+    util::join_words,
+    Action, ExecOptions, FeatureSelection, Target,
+};
+
+const HISTORY_FILE_NAME: &str = "history.toml";
+const MAX_HISTORY_LEN: usize = 1000;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+const FRECENCY_BONUS_WEIGHT: f64 = 16.0;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    // `state::target_key` of the target
+    pub target: String,
+    pub action: String,
+    // set if a single test function was selected
+    pub test: Option<String>,
+    #[serde(default)]
+    pub features: FeatureSelection,
+    pub profile: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub additional_args: Vec<String>,
+    // seconds since the unix epoch
+    pub timestamp: u64,
+    // `None` if terminated by a signal
+    pub status: Option<i32>,
+}
+
+impl HistoryEntry {
+    pub fn new(
+        target: &Target,
+        action: &Action,
+        test: Option<&str>,
+        options: &ExecOptions,
+        additional_args: &[String],
+        status: ExitStatus,
+    ) -> HistoryEntry {
+        HistoryEntry {
+            target: target_key(target),
+            action: action.name().into(),
+            test: test.map(Into::into),
+            features: options.features.clone(),
+            profile: options.profile.clone(),
+            args: options.args.clone(),
+            additional_args: additional_args.to_vec(),
+            timestamp: now(),
+            status: status.code(),
+        }
+    }
+
+    // text shown and matched in the history list
+    pub fn label(&self) -> String {
+        let mut label = self.target.clone();
+        if let Some(test) = &self.test {
+            label.push(' ');
+            label.push_str(test);
+        }
+        if !self.args.is_empty() {
+            label.push_str(" -- ");
+            label.push_str(&join_words(&self.args));
+        }
+        label
+    }
+
+    fn is_same_invocation(&self, other: &HistoryEntry) -> bool {
+        self.target == other.target
+            && self.action == other.action
+            && self.test == other.test
+            && self.features == other.features
+            && self.profile == other.profile
+            && self.args == other.args
+            && self.additional_args == other.additional_args
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WorkspaceHistory {
+    // oldest first
+    #[serde(default)]
+    entries: Vec<HistoryEntry>,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn load_all() -> std::io::Result<BTreeMap<String, WorkspaceHistory>> {
+    read_data_file(HISTORY_FILE_NAME)
+}
+
+// returns the entries of the workspace, oldest first
+pub fn load(workspace_root: &str) -> Vec<HistoryEntry> {
+    load_all()
+        .ok()
+        .and_then(|mut all| all.remove(workspace_root))
+        .map(|h| h.entries)
+        .unwrap_or_default()
+}
+
+pub fn record(workspace_root: &str, entry: HistoryEntry) -> std::io::Result<()> {
+    let Some(path) = data_file_path(HISTORY_FILE_NAME) else {
+        return Ok(());
+    };
+    let mut all = load_all()?;
+    let entries = &mut all.entry(workspace_root.to_string()).or_default().entries;
+    entries.push(entry);
+    if entries.len() > MAX_HISTORY_LEN {
+        entries.drain(..entries.len() - MAX_HISTORY_LEN);
+    }
+    let content = toml::to_string(&all).map_err(std::io::Error::other)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)
+}
+
+// newest first, keeping only the latest of the same invocations
+pub fn distinct_recent(entries: Vec<HistoryEntry>) -> Vec<HistoryEntry> {
+    let mut ret: Vec<HistoryEntry> = Vec::new();
+    for e in entries.into_iter().rev() {
+        if !ret.iter().any(|r| r.is_same_invocation(&e)) {
+            ret.push(e);
+        }
+    }
+    ret
+}
+
+pub fn format_age(timestamp: u64, now: u64) -> String {
+    let age = now.saturating_sub(timestamp);
+    match age {
+        a if a < 60 => format!("{a}s"),
+        a if a < HOUR => format!("{}m", a / 60),
+        a if a < DAY => format!("{}h", a / HOUR),
+        a if a < WEEK => format!("{}d", a / DAY),
+        a => format!("{}w", a / WEEK),
+    }
+}
+
+fn frecency_weight(age: u64) -> f64 {
+    match age {
+        a if a < HOUR => 4.0,
+        a if a < DAY => 2.0,
+        a if a < WEEK => 1.0,
+        _ => 0.25,
+    }
+}
+
+// frecency score keyed by `state::target_key`
+pub fn frecency(entries: &[HistoryEntry], now: u64) -> HashMap<String, f64> {
+    let mut scores = HashMap::new();
+    for e in entries {
+        let age = now.saturating_sub(e.timestamp);
+        *scores.entry(e.target.clone()).or_default() += frecency_weight(age);
+    }
+    scores
+}
+
+// added to the match score.
+// it grows slowly, so that a frequent target outranks only a slightly better match.
+pub fn frecency_bonus(frecency: f64) -> i64 {
+    (FRECENCY_BONUS_WEIGHT * frecency.ln_1p()).round() as i64
+}
+
+// the latest timestamp keyed by `state::target_key`
+pub fn last_runs(entries: &[HistoryEntry]) -> HashMap<String, u64> {
+    let mut ret: HashMap<String, u64> = HashMap::new();
+    for e in entries {
+        let ts = ret.entry(e.target.clone()).or_default();
+        *ts = (*ts).max(e.timestamp);
+    }
+    ret
+}
+
+// stable, so targets that have never been run keep their original order
+pub fn sort_by_frecency(targets: &mut [Target], scores: &HashMap<String, f64>) {
+    let score = |t: &Target| scores.get(target_key(t).as_str()).copied().unwrap_or(0.0);
+    targets.sort_by(|a, b| score(b).total_cmp(&score(a)));
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn entry(target: &str, timestamp: u64) -> HistoryEntry {
+        HistoryEntry {
+            target: target.into(),
+            action: "run".into(),
+            test: None,
+            features: FeatureSelection::default(),
+            profile: "dev".into(),
+            args: vec![],
+            additional_args: vec![],
+            timestamp,
+            status: Some(0),
+        }
+    }
+
+    #[rstest]
+    #[case(0, 4.0)]
+    #[case(HOUR - 1, 4.0)]
+    #[case(HOUR, 2.0)]
+    #[case(DAY, 1.0)]
+    #[case(WEEK, 0.25)]
+    fn test_frecency_weight(#[case] age: u64, #[case] expected: f64) {
+        assert_eq!(frecency_weight(age), expected);
+    }
+
+    #[rstest]
+    #[case(0, "0s")]
+    #[case(59, "59s")]
+    #[case(60, "1m")]
+    #[case(HOUR - 1, "59m")]
+    #[case(3 * HOUR, "3h")]
+    #[case(2 * DAY + HOUR, "2d")]
+    #[case(5 * WEEK, "5w")]
+    fn test_format_age(#[case] age: u64, #[case] expected: &str) {
+        let now = 10 * WEEK;
+        assert_eq!(format_age(now - age, now), expected);
+    }
+
+    #[test]
+    fn test_distinct_recent() {
+        let mut e3 = entry("a", 3);
+        e3.args = vec!["-v".into()];
+        let entries = vec![entry("a", 1), entry("b", 2), e3.clone(), entry("a", 4)];
+        let actual = distinct_recent(entries);
+        assert_eq!(actual, vec![entry("a", 4), e3, entry("b", 2)]);
+    }
+
+    #[test]
+    fn test_frecency() {
+        let now = 10 * WEEK;
+        let entries = vec![
+            entry("a", now - 2 * WEEK),
+            entry("b", now - 2 * DAY),
+            entry("a", now - 2 * WEEK),
+            entry("c", now - 10),
+            entry("b", now - 2 * HOUR),
+        ];
+        let scores = frecency(&entries, now);
+        assert_eq!(scores.len(), 3);
+        assert_eq!(scores["a"], 0.5);
+        assert_eq!(scores["b"], 3.0);
+        assert_eq!(scores["c"], 4.0);
+    }
+
+    #[rstest]
+    #[case(0.0, 0)]
+    #[case(4.0, 26)]
+    #[case(80.0, 70)]
+    fn test_frecency_bonus(#[case] frecency: f64, #[case] expected: i64) {
+        assert_eq!(frecency_bonus(frecency), expected);
+    }
+
+    #[test]
+    fn test_last_runs() {
+        let entries = vec![entry("a", 3), entry("b", 2), entry("a", 1)];
+        let actual = last_runs(&entries);
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual["a"], 3);
+        assert_eq!(actual["b"], 2);
+    }
+}
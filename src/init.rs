@@ -0,0 +1,87 @@
+use std::io::{stdout, Write};
+
+use clap::CommandFactory;
+use clap_complete::Shell;
+
+use crate::{Cli, InitShell};
+
+const BASH_WIDGET: &str = include_str!("shell/widget.bash");
+const ZSH_WIDGET: &str = include_str!("shell/widget.zsh");
+const FISH_WIDGET: &str = include_str!("shell/widget.fish");
+
+const BASH_COMPLETION: &str = include_str!("shell/completion.bash");
+const ZSH_COMPLETION: &str = include_str!("shell/completion.zsh");
+const FISH_COMPLETION: &str = include_str!("shell/completion.fish");
+
+const BIN_NAME: &str = "cargo-selector";
+
+pub fn print_init_script(shell: InitShell) -> std::io::Result<()> {
+    let mut out = stdout().lock();
+    out.write_all(init_script(shell).as_bytes())?;
+    out.flush()
+}
+
+// the key binding widget followed by the completions of `cargo-selector`,
+// which are also used for `cargo selector`
+fn init_script(shell: InitShell) -> String {
+    let (widget, completion, shell) = match shell {
+        InitShell::Bash => (BASH_WIDGET, BASH_COMPLETION, Shell::Bash),
+        InitShell::Zsh => (ZSH_WIDGET, ZSH_COMPLETION, Shell::Zsh),
+        InitShell::Fish => (FISH_WIDGET, FISH_COMPLETION, Shell::Fish),
+    };
+
+    // generated for `cargo-selector`, since those of `cargo` itself should not be overwritten.
+    // `completion` delegates `cargo selector` to them and the rest to the original ones.
+    let mut cmd = Cli::command()
+        .find_subcommand("selector")
+        .unwrap()
+        .clone()
+        .name(BIN_NAME)
+        .bin_name(BIN_NAME);
+    let mut completions = Vec::new();
+    clap_complete::generate(shell, &mut cmd, BIN_NAME, &mut completions);
+
+    format!(
+        "{widget}\n{}\n{completion}",
+        String::from_utf8_lossy(&completions)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(
+        InitShell::Bash,
+        "bind -m emacs-standard -x",
+        "complete -F _cargo__selector",
+        "complete -F __cargo_selector_complete -o bashdefault -o default cargo\n"
+    )]
+    #[case(
+        InitShell::Zsh,
+        "zle -N __cargo_selector_widget",
+        "#compdef cargo-selector",
+        "compdef __cargo_selector_complete cargo\n"
+    )]
+    #[case(
+        InitShell::Fish,
+        "bind \\cx\\cr",
+        "complete -c cargo-selector",
+        "complete -c cargo -n '__fish_seen_subcommand_from selector'"
+    )]
+    fn test_init_script(
+        #[case] shell: InitShell,
+        #[case] widget: &str,
+        #[case] completion: &str,
+        #[case] cargo_completion: &str,
+    ) {
+        let script = init_script(shell);
+        assert!(script.contains(widget));
+        assert!(script.contains(completion));
+        assert!(script.contains(cargo_completion));
+        assert!(script.contains("inline-list-size"));
+    }
+}
@@ -0,0 +1,288 @@
+// widths of the columns of the target list.
+// as the terminal gets narrower, the columns are shrunk down to their minimum widths,
+// and then dropped in the order of `DROP_ORDER`.
+
+use serde::{de, Deserialize, Deserializer};
+
+use crate::config::{ColumnOptions, ColumnsConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Kind,
+    Name,
+    Package,
+    Path,
+    Features,
+    Description,
+    LastRun,
+}
+
+// the first one is dropped first
+const DROP_ORDER: [Column; 7] = [
+    Column::Features,
+    Column::Description,
+    Column::LastRun,
+    Column::Package,
+    Column::Path,
+    Column::Kind,
+    Column::Name,
+];
+
+// the list is not usable if not even the name column fits
+pub const MIN_WIDTH: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnWidth {
+    // number of cells
+    Fixed(usize),
+    // share of the width left by the fixed columns, like `2fr`
+    Fraction(usize),
+}
+
+impl<'de> Deserialize<'de> for ColumnWidth {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Fixed(usize),
+            Fraction(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Fixed(w) => Ok(ColumnWidth::Fixed(w)),
+            Raw::Fraction(s) => s
+                .strip_suffix("fr")
+                .and_then(|n| n.parse().ok())
+                .filter(|n| *n > 0)
+                .map(ColumnWidth::Fraction)
+                .ok_or_else(|| de::Error::custom(format!("invalid column width: {s}"))),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Align {
+    #[default]
+    Left,
+    Right,
+    Center,
+}
+
+// the side where the text is cut off if it is too long
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Truncate {
+    Left,
+    #[default]
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnSpec {
+    pub column: Column,
+    pub width: ColumnWidth,
+    pub min_width: usize,
+    pub align: Align,
+    pub truncate: Truncate,
+}
+
+impl ColumnSpec {
+    fn new(column: Column, options: &ColumnOptions) -> ColumnSpec {
+        let (width, min_width, align, truncate) = match column {
+            Column::Kind => (ColumnWidth::Fixed(7), 7, Align::Left, Truncate::Right),
+            Column::Name => (ColumnWidth::Fixed(25), 10, Align::Left, Truncate::Right),
+            Column::Package => (ColumnWidth::Fixed(20), 10, Align::Left, Truncate::Right),
+            // keep the file name visible
+            Column::Path => (ColumnWidth::Fraction(1), 10, Align::Left, Truncate::Left),
+            Column::Features => (ColumnWidth::Fraction(1), 10, Align::Left, Truncate::Right),
+            Column::Description => (ColumnWidth::Fraction(1), 10, Align::Left, Truncate::Right),
+            Column::LastRun => (ColumnWidth::Fixed(4), 4, Align::Right, Truncate::Right),
+        };
+        let width = options.width.unwrap_or(width);
+        let min_width = match width {
+            ColumnWidth::Fixed(w) => w.min(min_width),
+            ColumnWidth::Fraction(_) => min_width,
+        };
+        ColumnSpec {
+            column,
+            width,
+            min_width,
+            align: options.align.unwrap_or(align),
+            truncate: options.truncate.unwrap_or(truncate),
+        }
+    }
+
+    fn drop_order(&self) -> usize {
+        DROP_ORDER
+            .iter()
+            .position(|c| *c == self.column)
+            .unwrap_or_default()
+    }
+}
+
+pub fn column_specs(config: &ColumnsConfig) -> Vec<ColumnSpec> {
+    config
+        .show
+        .iter()
+        .map(|c| ColumnSpec::new(*c, config.options(*c)))
+        .collect()
+}
+
+pub fn list_columns(max_w: usize, specs: &[ColumnSpec]) -> Vec<(ColumnSpec, usize)> {
+    let mut specs = specs.to_vec();
+    loop {
+        if let Some(widths) = fit(&specs, max_w) {
+            return specs.into_iter().zip(widths).collect();
+        }
+        let Some(i) = (0..specs.len()).min_by_key(|i| specs[*i].drop_order()) else {
+            return Vec::new();
+        };
+        if specs.len() == 1 {
+            // nothing fits, but show the most important one as much as possible
+            return vec![(specs[i], max_w.saturating_sub(2))];
+        }
+        specs.remove(i);
+    }
+}
+
+// each column is preceded by a space, and the line ends with a space
+fn fit(specs: &[ColumnSpec], max_w: usize) -> Option<Vec<usize>> {
+    let available = max_w.checked_sub(specs.len() + 1)?;
+    let min_total: usize = specs.iter().map(|s| s.min_width).sum();
+    if min_total > available {
+        return None;
+    }
+    let mut widths: Vec<usize> = specs
+        .iter()
+        .map(|s| match s.width {
+            ColumnWidth::Fixed(w) => w,
+            ColumnWidth::Fraction(_) => s.min_width,
+        })
+        .collect();
+    let mut total: usize = widths.iter().sum();
+    // shrink the columns in the order they would be dropped
+    let mut order: Vec<usize> = (0..specs.len()).collect();
+    order.sort_by_key(|i| specs[*i].drop_order());
+    for i in order {
+        if total <= available {
+            break;
+        }
+        let cut = (total - available).min(widths[i] - specs[i].min_width);
+        widths[i] -= cut;
+        total -= cut;
+    }
+    // the rest is shared by the fraction columns, and the remainder goes to the last one
+    let fractions: Vec<(usize, usize)> = specs
+        .iter()
+        .enumerate()
+        .filter_map(|(i, s)| match s.width {
+            ColumnWidth::Fraction(n) => Some((i, n)),
+            ColumnWidth::Fixed(_) => None,
+        })
+        .collect();
+    let fraction_total: usize = fractions.iter().map(|(_, n)| n).sum();
+    let rest = available - total;
+    let mut given = 0;
+    for (k, (i, n)) in fractions.iter().enumerate() {
+        let w = if k + 1 == fractions.len() {
+            rest - given
+        } else {
+            rest * n / fraction_total
+        };
+        widths[*i] += w;
+        given += w;
+    }
+    Some(widths)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    use Column::*;
+
+    fn specs(show: &[Column]) -> Vec<ColumnSpec> {
+        let config = ColumnsConfig {
+            show: show.to_vec(),
+            ..Default::default()
+        };
+        column_specs(&config)
+    }
+
+    const DEFAULT: &[Column] = &[Kind, Name, Path, Features];
+    const NO_FEATURES: &[Column] = &[Kind, Name, Path];
+
+    #[rstest]
+    #[case(100, DEFAULT, vec![(Kind, 7), (Name, 25), (Path, 31), (Features, 32)])]
+    #[case(77, DEFAULT, vec![(Kind, 7), (Name, 25), (Path, 20), (Features, 20)])]
+    #[case(57, DEFAULT, vec![(Kind, 7), (Name, 25), (Path, 10), (Features, 10)])]
+    #[case(42, DEFAULT, vec![(Kind, 7), (Name, 10), (Path, 10), (Features, 10)])]
+    #[case(41, DEFAULT, vec![(Kind, 7), (Name, 20), (Path, 10)])]
+    #[case(100, NO_FEATURES, vec![(Kind, 7), (Name, 25), (Path, 64)])]
+    #[case(48, NO_FEATURES, vec![(Kind, 7), (Name, 25), (Path, 12)])]
+    #[case(31, NO_FEATURES, vec![(Kind, 7), (Name, 10), (Path, 10)])]
+    #[case(30, NO_FEATURES, vec![(Kind, 7), (Name, 20)])]
+    #[case(20, NO_FEATURES, vec![(Kind, 7), (Name, 10)])]
+    #[case(19, NO_FEATURES, vec![(Name, 17)])]
+    #[case(12, NO_FEATURES, vec![(Name, 10)])]
+    #[case(11, NO_FEATURES, vec![(Name, 9)])]
+    #[case(1, NO_FEATURES, vec![(Name, 0)])]
+    #[case(0, DEFAULT, vec![(Name, 0)])]
+    #[case(100, &[Name, Package, LastRun], vec![(Name, 25), (Package, 20), (LastRun, 4)])]
+    #[case(40, &[Name, Package, LastRun], vec![(Name, 22), (Package, 10), (LastRun, 4)])]
+    #[case(80, &[Path, Name, Description], vec![(Path, 25), (Name, 25), (Description, 26)])]
+    #[case(30, &[Path, Description], vec![(Path, 13), (Description, 14)])]
+    #[case(10, &[Path, Description], vec![(Path, 8)])]
+    fn test_list_columns(
+        #[case] max_w: usize,
+        #[case] show: &[Column],
+        #[case] expected: Vec<(Column, usize)>,
+    ) {
+        let actual: Vec<(Column, usize)> = list_columns(max_w, &specs(show))
+            .into_iter()
+            .map(|(s, w)| (s.column, w))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_column_specs_options() {
+        let config: ColumnsConfig = toml::from_str(
+            r#"
+            show = ["path", "name"]
+            path = { width = "2fr", truncate = "right" }
+            name = { width = 5, align = "right" }
+            "#,
+        )
+        .unwrap();
+        let actual = column_specs(&config);
+        let expected = vec![
+            ColumnSpec {
+                column: Path,
+                width: ColumnWidth::Fraction(2),
+                min_width: 10,
+                align: Align::Left,
+                truncate: Truncate::Right,
+            },
+            ColumnSpec {
+                column: Name,
+                width: ColumnWidth::Fixed(5),
+                min_width: 5,
+                align: Align::Right,
+                truncate: Truncate::Right,
+            },
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case("width = \"fr\"")]
+    #[case("width = \"0fr\"")]
+    #[case("width = \"50%\"")]
+    fn test_column_width_invalid(#[case] s: &str) {
+        assert!(toml::from_str::<ColumnOptions>(s).is_err());
+    }
+}
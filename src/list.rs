@@ -0,0 +1,188 @@
+use std::{
+    borrow::Cow,
+    io::{stdout, Write},
+};
+
+use serde::Serialize;
+
+use crate::{cargo, Action, ExecOptions, ListFormat, Target};
+
+#[derive(Debug, Serialize)]
+struct TargetInfo<'a> {
+    name: &'a str,
+    package: &'a str,
+    kind: &'static str,
+    path: &'a str,
+    required_features: &'a [String],
+    command: String,
+}
+
+impl<'a> TargetInfo<'a> {
+    fn new(target: &'a Target, additional_args: &[String]) -> TargetInfo<'a> {
+        let options = ExecOptions::default();
+        TargetInfo {
+            name: &target.name,
+            package: &target.package,
+            kind: target.kind.name(),
+            path: &target.path,
+            required_features: &target.required_features,
+            command: cargo::command_line(target, &Action::Run, &options, additional_args),
+        }
+    }
+}
+
+pub fn print_targets(
+    targets: &[Target],
+    format: ListFormat,
+    additional_args: &[String],
+) -> std::io::Result<()> {
+    let infos: Vec<TargetInfo> = targets
+        .iter()
+        .map(|t| TargetInfo::new(t, additional_args))
+        .collect();
+    let mut out = stdout().lock();
+    out.write_all(format_targets(&infos, format).as_bytes())?;
+    out.flush()
+}
+
+fn format_targets(infos: &[TargetInfo], format: ListFormat) -> String {
+    match format {
+        ListFormat::Plain => format_plain(infos),
+        ListFormat::Json => serde_json::to_string_pretty(infos).unwrap() + "\n",
+        ListFormat::Tsv => format_tsv(infos),
+    }
+}
+
+// the columns are aligned, and the required features are `-` if there are none
+fn format_plain(infos: &[TargetInfo]) -> String {
+    let rows: Vec<[Cow<str>; 6]> = infos
+        .iter()
+        .map(|i| {
+            let features = if i.required_features.is_empty() {
+                "-".into()
+            } else {
+                i.required_features.join(",").into()
+            };
+            [
+                i.kind.into(),
+                i.name.into(),
+                i.package.into(),
+                i.path.into(),
+                features,
+                i.command.as_str().into(),
+            ]
+        })
+        .collect();
+    let mut widths = [0; 6];
+    for row in &rows {
+        for (w, s) in widths.iter_mut().zip(row) {
+            *w = (*w).max(console::measure_text_width(s));
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            let mut line = String::new();
+            for (k, (s, w)) in row.iter().zip(widths).enumerate() {
+                line.push_str(s);
+                // the last column is not padded
+                if k + 1 < row.len() {
+                    line.push_str(&" ".repeat(w - console::measure_text_width(s) + 1));
+                }
+            }
+            line + "\n"
+        })
+        .collect()
+}
+
+fn format_tsv(infos: &[TargetInfo]) -> String {
+    // tabs and newlines would break the columns
+    let escape = |s: &str| s.replace(['\t', '\n'], " ");
+    let mut lines = vec!["name\tpackage\tkind\tpath\trequired_features\tcommand".to_string()];
+    lines.extend(infos.iter().map(|i| {
+        [
+            escape(i.name),
+            escape(i.package),
+            i.kind.to_string(),
+            escape(i.path),
+            escape(&i.required_features.join(",")),
+            escape(&i.command),
+        ]
+        .join("\t")
+    }));
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::TargetKind;
+
+    use super::*;
+
+    fn target(name: &str, kind: TargetKind, path: &str, required_features: Vec<&str>) -> Target {
+        Target {
+            required_features: required_features.into_iter().map(Into::into).collect(),
+            ..Target::fixture(name, kind, path)
+        }
+    }
+
+    fn targets() -> Vec<Target> {
+        vec![
+            target("xyz", TargetKind::Bin, "src/main.rs", vec![]),
+            target(
+                "hello",
+                TargetKind::Example,
+                "examples/hello.rs",
+                vec!["a", "b"],
+            ),
+        ]
+    }
+
+    #[rstest]
+    #[case(
+        ListFormat::Plain,
+        "bin     xyz   pkg src/main.rs       -   cargo run --bin xyz --package pkg
+example hello pkg examples/hello.rs a,b cargo run --example hello --package pkg --features \"a b\"
+"
+    )]
+    #[case(
+        ListFormat::Tsv,
+        "name\tpackage\tkind\tpath\trequired_features\tcommand
+xyz\tpkg\tbin\tsrc/main.rs\t\tcargo run --bin xyz --package pkg
+hello\tpkg\texample\texamples/hello.rs\ta,b\tcargo run --example hello --package pkg --features \"a b\"
+"
+    )]
+    fn test_format_targets(#[case] format: ListFormat, #[case] expected: &str) {
+        let targets = targets();
+        let infos: Vec<TargetInfo> = targets.iter().map(|t| TargetInfo::new(t, &[])).collect();
+        assert_eq!(format_targets(&infos, format), expected);
+    }
+
+    #[test]
+    fn test_format_targets_json() {
+        let targets = targets();
+        let infos: Vec<TargetInfo> = targets.iter().map(|t| TargetInfo::new(t, &[])).collect();
+        let actual: serde_json::Value =
+            serde_json::from_str(&format_targets(&infos, ListFormat::Json)).unwrap();
+        let expected = serde_json::json!([
+            {
+                "name": "xyz",
+                "package": "pkg",
+                "kind": "bin",
+                "path": "src/main.rs",
+                "required_features": [],
+                "command": "cargo run --bin xyz --package pkg",
+            },
+            {
+                "name": "hello",
+                "package": "pkg",
+                "kind": "example",
+                "path": "examples/hello.rs",
+                "required_features": ["a", "b"],
+                "command": "cargo run --example hello --package pkg --features \"a b\"",
+            },
+        ]);
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,17 +1,24 @@
 mod cargo;
 mod config;
 mod event;
+mod history;
+mod init;
+mod layout;
+mod list;
 mod matcher;
+mod preview;
+mod state;
 mod tui;
 mod util;
 
 use std::{
+    collections::BTreeMap,
     io::{stderr, BufWriter, Stderr},
     panic,
     process::{ExitCode, ExitStatus},
 };
 
-use clap::{Args, Parser, ValueEnum};
+use clap::{error::ErrorKind, Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use ratatui::{
     backend::CrosstermBackend,
     crossterm::{
@@ -20,12 +27,14 @@ use ratatui::{
     },
     Terminal, TerminalOptions, Viewport,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    config::Config,
+    config::{Config, CustomAction},
+    history::HistoryEntry,
     matcher::Matcher,
     tui::{Ret, Tui},
+    util::split_words,
 };
 
 #[cfg(unix)]
@@ -38,14 +47,23 @@ enum Cli {
 }
 
 #[derive(Debug, Args)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct SelectorArgs {
+    #[command(subcommand)]
+    command: Option<SelectorCommand>,
+
     /// Display list inline
     #[arg(short, long)]
     inline: bool,
 
     /// List size
-    #[arg(short = 'n', long, default_value = "10", value_name = "SIZE")]
+    #[arg(
+        short = 'n',
+        long,
+        default_value = "10",
+        value_name = "SIZE",
+        value_parser = clap::value_parser!(u16).range(1..u16::MAX as i64)
+    )]
     inline_list_size: u16,
 
     /// Target kind
@@ -56,24 +74,178 @@ struct SelectorArgs {
     #[arg(short = 't', long, value_name = "TYPE")]
     match_type: Option<MatchType>,
 
+    /// Case sensitivity of matching [default: smart]
+    #[arg(long, value_name = "CASE")]
+    case: Option<Case>,
+
     /// Additional arguments
     #[arg(short, long, value_name = "ARGS", allow_hyphen_values = true)]
     additional_args: Option<String>,
+
+    /// Re-execute the last command without opening the selector
+    #[arg(short, long, conflicts_with = "history")]
+    last: bool,
+
+    /// Select from the execution history
+    #[arg(long)]
+    history: bool,
+
+    /// Print the targets and exit without opening the selector
+    #[arg(long, conflicts_with_all = ["last", "history"])]
+    list: bool,
+
+    /// Output format of --list
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "plain",
+        requires = "list"
+    )]
+    format: ListFormat,
+
+    /// Start with the given query
+    #[arg(short, long, value_name = "QUERY")]
+    query: Option<String>,
+
+    /// Select automatically if there is only one match
+    #[arg(short = '1', long)]
+    select_1: bool,
+
+    /// Exit with an error without opening the selector if there is no match
+    #[arg(short = '0', long)]
+    exit_0: bool,
+
+    /// Print the selected command instead of executing it
+    #[arg(long, visible_alias = "dry-run")]
+    print: bool,
+
+    /// Arguments passed to the target after `--`
+    #[arg(last = true, value_name = "ARGS")]
+    args: Vec<String>,
+}
+
+#[derive(Debug, Subcommand)]
+enum SelectorCommand {
+    /// Print the key binding and completion script for the shell
+    Init {
+        #[arg(value_name = "SHELL")]
+        shell: InitShell,
+    },
 }
 
 #[derive(Debug, Clone)]
 pub struct Target {
     name: String,
     package: String,
+    package_dir: String,
     kind: TargetKind,
     path: String,
+    src_path: String,
     required_features: Vec<String>,
+    package_features: BTreeMap<String, Vec<String>>,
+    package_description: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+// shared by the tests of the modules
+#[cfg(test)]
+impl Target {
+    // a target of the package `pkg` in `/path/to/pkg`, with no features
+    fn fixture(name: &str, kind: TargetKind, path: &str) -> Target {
+        Target {
+            name: name.into(),
+            package: "pkg".into(),
+            package_dir: "/path/to/pkg".into(),
+            kind,
+            path: path.into(),
+            src_path: format!("/path/to/pkg/{path}"),
+            required_features: vec![],
+            package_features: BTreeMap::new(),
+            package_description: None,
+        }
+    }
+}
+
+pub struct Workspace {
+    root: String,
+    targets: Vec<Target>,
+    profiles: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecOptions {
+    features: FeatureSelection,
+    profile: String,
+    // passed to the target after `--`
+    args: Vec<String>,
+}
+
+impl Default for ExecOptions {
+    fn default() -> Self {
+        Self {
+            features: FeatureSelection::default(),
+            profile: cargo::DEV_PROFILE.into(),
+            args: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeatureSelection {
+    features: Vec<String>,
+    no_default_features: bool,
+    all_features: bool,
+}
+
+impl FeatureSelection {
+    fn is_empty(&self) -> bool {
+        self.features.is_empty() && !self.no_default_features && !self.all_features
+    }
+
+    fn toggle_feature(&mut self, feature: &str) {
+        if let Some(pos) = self.features.iter().position(|f| f == feature) {
+            self.features.remove(pos);
+        } else {
+            self.features.push(feature.to_string());
+            self.features.sort();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TargetKind {
     Bin,
     Example,
+    Test,
+    Bench,
+    Lib,
+}
+
+impl TargetKind {
+    fn name(self) -> &'static str {
+        match self {
+            TargetKind::Bin => "bin",
+            TargetKind::Example => "example",
+            TargetKind::Test => "test",
+            TargetKind::Bench => "bench",
+            TargetKind::Lib => "lib",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InitShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ListFormat {
+    Plain,
+    Json,
+    Tsv,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
@@ -82,22 +254,144 @@ pub enum MatchType {
     #[default]
     Substring,
     Fuzzy,
+    Regex,
+    Prefix,
+    Exact,
 }
 
 impl MatchType {
+    fn name(self) -> &'static str {
+        match self {
+            MatchType::Substring => "substring",
+            MatchType::Fuzzy => "fuzzy",
+            MatchType::Regex => "regex",
+            MatchType::Prefix => "prefix",
+            MatchType::Exact => "exact",
+        }
+    }
+
+    fn next(self) -> MatchType {
+        let types = MatchType::value_variants();
+        let pos = types.iter().position(|t| *t == self).unwrap_or(0);
+        types[(pos + 1) % types.len()]
+    }
+
     fn matcher(self) -> Matcher {
         match self {
             MatchType::Substring => Matcher::substring(),
             MatchType::Fuzzy => Matcher::fuzzy(),
+            MatchType::Regex => Matcher::regex(),
+            MatchType::Prefix => Matcher::prefix(),
+            MatchType::Exact => Matcher::exact(),
         }
     }
 }
 
-#[derive(Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Case {
+    // case-insensitive unless the query contains an uppercase char
+    #[default]
+    Smart,
+    Sensitive,
+    Insensitive,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub enum Action {
     #[default]
     Run,
     Build,
+    Check,
+    Clippy,
+    Doc,
+    Test,
+    Bench,
+    Install,
+    Asm,
+    Custom(CustomAction),
+}
+
+impl Action {
+    fn all(custom_actions: Vec<CustomAction>) -> Vec<Action> {
+        let builtin = [
+            Action::Run,
+            Action::Build,
+            Action::Check,
+            Action::Clippy,
+            Action::Doc,
+            Action::Test,
+            Action::Bench,
+            Action::Install,
+            Action::Asm,
+        ];
+        builtin
+            .into_iter()
+            .chain(custom_actions.into_iter().map(Action::Custom))
+            .collect()
+    }
+
+    fn find(actions: &[Action], name: &str) -> Option<Action> {
+        actions.iter().find(|a| a.name() == name).cloned()
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Action::Run => "run",
+            Action::Build => "build",
+            Action::Check => "check",
+            Action::Clippy => "clippy",
+            Action::Doc => "doc",
+            Action::Test => "test",
+            Action::Bench => "bench",
+            Action::Install => "install",
+            Action::Asm => "asm",
+            Action::Custom(c) => &c.name,
+        }
+    }
+
+    fn is_applicable(&self, kind: TargetKind) -> bool {
+        use TargetKind::*;
+        match self {
+            // a lib cannot be run, and `Test` is used instead
+            Action::Run => kind != Lib,
+            Action::Build | Action::Check | Action::Clippy | Action::Test => true,
+            Action::Doc => matches!(kind, Bin | Lib),
+            Action::Bench => matches!(kind, Bin | Bench | Lib),
+            Action::Install => matches!(kind, Bin | Example),
+            Action::Asm => matches!(kind, Bin | Example | Lib),
+            Action::Custom(c) => c.kinds.is_empty() || c.kinds.contains(&kind),
+        }
+    }
+
+    // whether the args typed in the editor are passed to the target
+    fn takes_args(&self) -> bool {
+        matches!(
+            self,
+            Action::Run | Action::Test | Action::Bench | Action::Custom(_)
+        )
+    }
+
+    fn cycle(&self, actions: &[Action], kind: Option<TargetKind>, forward: bool) -> Action {
+        let n = actions.len();
+        let pos = actions.iter().position(|a| a == self).unwrap_or(0);
+        (1..n)
+            .map(|i| {
+                let i = if forward { pos + i } else { pos + n - i };
+                &actions[i % n]
+            })
+            .find(|a| kind.is_none_or(|k| a.is_applicable(k)))
+            .unwrap_or(self)
+            .clone()
+    }
+
+    fn next(&self, actions: &[Action], kind: Option<TargetKind>) -> Action {
+        self.cycle(actions, kind, true)
+    }
+
+    fn prev(&self, actions: &[Action], kind: Option<TargetKind>) -> Action {
+        self.cycle(actions, kind, false)
+    }
 }
 
 fn setup(
@@ -154,45 +448,462 @@ fn to_exit_code(status: ExitStatus) -> ExitCode {
     }
 }
 
+// `args` are passed after `--`, following any arguments already put there by `additional_args`
+fn build_additional_args(
+    additional_args: Option<String>,
+    args: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let mut additional_args = match additional_args {
+        Some(s) => split_words(&s)?,
+        None => Vec::new(),
+    };
+    if !args.is_empty() {
+        if !additional_args.iter().any(|a| a == "--") {
+            additional_args.push("--".into());
+        }
+        additional_args.extend(args);
+    }
+    Ok(additional_args)
+}
+
+fn resolve_history_entry(
+    entry: &HistoryEntry,
+    targets: &[Target],
+    actions: &[Action],
+) -> Option<(Target, Action)> {
+    let target = targets
+        .iter()
+        .find(|t| state::target_key(t) == entry.target)?;
+    let action = Action::find(actions, &entry.action)?;
+    Some((target.clone(), action))
+}
+
+// the result of `--exit-0` / `--select-1`, or `None` to show the tui
+fn preselect(tui: &Tui, exit_0: bool, select_1: bool) -> Result<Option<Ret>, String> {
+    if !exit_0 && !select_1 {
+        return Ok(None);
+    }
+    // the filtered list is stale while the query is invalid
+    if let Some(e) = tui.query_error() {
+        return Err(format!("invalid query: {e}"));
+    }
+    match tui.matched_len() {
+        0 if exit_0 => Err("no target matched".into()),
+        1 if select_1 => Ok(Some(tui.selected())),
+        _ => Ok(None),
+    }
+}
+
+fn exec_history_entry(
+    root: &str,
+    entry: HistoryEntry,
+    target: Target,
+    action: Action,
+    print: bool,
+) -> ExitCode {
+    let options = ExecOptions {
+        features: entry.features,
+        profile: entry.profile,
+        args: entry.args,
+    };
+    let additional_args = entry.additional_args;
+    if print {
+        let line = match &entry.test {
+            Some(name) => cargo::test_command_line(&target, name, &options, &additional_args),
+            None => cargo::command_line(&target, &action, &options, &additional_args),
+        };
+        println!("{line}");
+        return ExitCode::SUCCESS;
+    }
+    let status = match &entry.test {
+        Some(name) => cargo::exec_cargo_test(&target, name, &options, &additional_args),
+        None => cargo::exec_cargo_run(&target, &action, &options, &additional_args),
+    };
+    let status = match status {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let entry = HistoryEntry::new(
+        &target,
+        &action,
+        entry.test.as_deref(),
+        &options,
+        &additional_args,
+        status,
+    );
+    if let Err(e) = history::record(root, entry) {
+        eprintln!("failed to save history: {e}");
+    }
+    to_exit_code(status)
+}
+
 fn main() -> std::io::Result<ExitCode> {
     let Cli::Selector(args) = Cli::parse();
     let SelectorArgs {
+        command,
         inline,
         inline_list_size,
         kind,
         match_type,
+        case,
         additional_args,
+        last,
+        history,
+        list,
+        format,
+        query,
+        select_1,
+        exit_0,
+        print,
+        args,
     } = args;
 
+    if let Some(SelectorCommand::Init { shell }) = command {
+        init::print_init_script(shell)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let additional_args = match build_additional_args(additional_args, args) {
+        Ok(args) => args,
+        Err(e) => {
+            let msg = format!("invalid value for '--additional-args <ARGS>': {e}");
+            let mut cmd = Cli::command();
+            cmd.build();
+            let cmd = cmd.find_subcommand_mut("selector").unwrap();
+            cmd.error(ErrorKind::InvalidValue, msg).exit();
+        }
+    };
+
     let config = Config::load();
     let match_type = match_type.or(config.match_type).unwrap_or_default();
+    let case = case.or(config.case).unwrap_or_default();
     let theme = config.color;
+    let actions = Action::all(config.actions);
+
+    let Workspace {
+        root,
+        mut targets,
+        profiles,
+    } = cargo::get_workspace();
+
+    let entries = history::load(&root);
+    if last {
+        let Some(entry) = entries.last() else {
+            eprintln!("no command in the history");
+            return Ok(ExitCode::FAILURE);
+        };
+        let Some((target, action)) = resolve_history_entry(entry, &targets, &actions) else {
+            eprintln!("the last command is no longer available: {}", entry.label());
+            return Ok(ExitCode::FAILURE);
+        };
+        return Ok(exec_history_entry(
+            &root,
+            entry.clone(),
+            target,
+            action,
+            print,
+        ));
+    }
+    // entries which cannot be executed anymore are not shown
+    let history_entries: Vec<HistoryEntry> = if history {
+        history::distinct_recent(entries.clone())
+            .into_iter()
+            .filter(|e| resolve_history_entry(e, &targets, &actions).is_some())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let all_targets = targets.clone();
 
-    let mut targets = cargo::get_all_targets();
     if let Some(kind) = kind {
         targets.retain(|t| t.kind == kind);
     }
 
-    initialize_panic_handler(inline);
-    let mut terminal = setup(inline, inline_list_size)?;
-    let term_size = terminal.get_frame().area();
-    let matcher = match_type.matcher();
-    let ret = Tui::new(targets, term_size, matcher, theme).run(&mut terminal);
-    shutdown(inline)?;
+    if list {
+        list::print_targets(&targets, format, &additional_args)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let workspace_state = state::load(&root);
+    let frecency = history::frecency(&entries, history::now());
+    history::sort_by_frecency(&mut targets, &frecency);
 
-    if inline {
-        terminal.clear()?;
+    let mut tui = Tui::new(
+        targets,
+        actions.clone(),
+        profiles,
+        workspace_state.clone(),
+        match_type,
+        case,
+        &config.columns,
+        theme,
+    );
+    tui.set_last_runs(history::last_runs(&entries));
+    tui.set_frecency(frecency);
+    tui.set_grouped(config.group_by_package);
+    tui.set_preview(&config.preview);
+    if history {
+        tui.enter_history(history_entries);
+    }
+    if let Some(query) = query {
+        tui.set_query(&query);
     }
 
+    // decided before setting up the terminal, so that nothing is drawn
+    let ret = match preselect(&tui, exit_0, select_1) {
+        Err(e) => {
+            eprintln!("{e}");
+            return Ok(ExitCode::FAILURE);
+        }
+        Ok(Some(ret)) => Ok(ret),
+        Ok(None) => {
+            initialize_panic_handler(inline);
+            let mut terminal = setup(inline, inline_list_size)?;
+            let ret = tui.run(&mut terminal);
+            shutdown(inline)?;
+            if inline {
+                terminal.clear()?;
+            }
+            ret
+        }
+    };
+
+    let new_workspace_state = tui.workspace_state();
+    if new_workspace_state != workspace_state {
+        if let Err(e) = state::save(&root, new_workspace_state) {
+            eprintln!("failed to save state: {e}");
+        }
+    }
+
+    let record = |entry: HistoryEntry| {
+        if let Err(e) = history::record(&root, entry) {
+            eprintln!("failed to save history: {e}");
+        }
+    };
+
     ret.map(|t| match t {
         Ret::Quit => ExitCode::SUCCESS,
-        Ret::Selected(t, a) => {
-            let status = cargo::exec_cargo_run(&t, &a, additional_args);
+        Ret::Selected(t, a, o) if print => {
+            println!("{}", cargo::command_line(&t, &a, &o, &additional_args));
+            ExitCode::SUCCESS
+        }
+        Ret::Selected(t, a, o) => {
+            let status = match cargo::exec_cargo_run(&t, &a, &o, &additional_args) {
+                Ok(status) => status,
+                Err(e) => {
+                    eprintln!("{e}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            record(HistoryEntry::new(
+                &t,
+                &a,
+                None,
+                &o,
+                &additional_args,
+                status,
+            ));
             to_exit_code(status)
         }
+        Ret::SelectedTest(t, name, o) if print => {
+            println!(
+                "{}",
+                cargo::test_command_line(&t, &name, &o, &additional_args)
+            );
+            ExitCode::SUCCESS
+        }
+        Ret::SelectedTest(t, name, o) => {
+            let status = match cargo::exec_cargo_test(&t, &name, &o, &additional_args) {
+                Ok(status) => status,
+                Err(e) => {
+                    eprintln!("{e}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            let entry =
+                HistoryEntry::new(&t, &Action::Test, Some(&name), &o, &additional_args, status);
+            record(entry);
+            to_exit_code(status)
+        }
+        Ret::SelectedHistory(entry) => {
+            match resolve_history_entry(&entry, &all_targets, &actions) {
+                Some((target, action)) => exec_history_entry(&root, entry, target, action, print),
+                None => ExitCode::FAILURE,
+            }
+        }
         Ret::NotSelected => {
             eprintln!("no command selected");
             ExitCode::SUCCESS
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Color;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn custom_action(name: &str, kinds: Vec<TargetKind>) -> CustomAction {
+        CustomAction {
+            name: name.into(),
+            command: "echo {name}".into(),
+            bg: Color::White,
+            fg: Color::Black,
+            kinds,
+        }
+    }
+
+    #[rstest]
+    #[case(Action::Run, None, Action::Build)]
+    #[case(Action::Asm, None, Action::Run)]
+    #[case(Action::Clippy, Some(TargetKind::Example), Action::Test)]
+    #[case(Action::Test, Some(TargetKind::Test), Action::Run)]
+    #[case(Action::Bench, Some(TargetKind::Bin), Action::Install)]
+    fn test_action_next(
+        #[case] action: Action,
+        #[case] kind: Option<TargetKind>,
+        #[case] expected: Action,
+    ) {
+        let actions = Action::all(vec![]);
+        assert_eq!(action.next(&actions, kind), expected);
+    }
+
+    #[rstest]
+    #[case(Action::Run, None, Action::Asm)]
+    #[case(Action::Run, Some(TargetKind::Test), Action::Test)]
+    #[case(Action::Test, Some(TargetKind::Example), Action::Clippy)]
+    fn test_action_prev(
+        #[case] action: Action,
+        #[case] kind: Option<TargetKind>,
+        #[case] expected: Action,
+    ) {
+        let actions = Action::all(vec![]);
+        assert_eq!(action.prev(&actions, kind), expected);
+    }
+
+    #[rstest]
+    #[case(Action::Asm, Some(TargetKind::Bin), "flame")]
+    #[case(Action::Asm, Some(TargetKind::Test), "flame")]
+    #[case(Action::Test, Some(TargetKind::Test), "flame")]
+    fn test_action_next_to_custom(
+        #[case] action: Action,
+        #[case] kind: Option<TargetKind>,
+        #[case] expected: &str,
+    ) {
+        let actions = Action::all(vec![
+            custom_action("flame", vec![]),
+            custom_action("watch", vec![TargetKind::Bin]),
+        ]);
+        let expected = Action::Custom(custom_action(expected, vec![]));
+        assert_eq!(action.next(&actions, kind), expected);
+    }
+
+    #[rstest]
+    #[case("1", true)]
+    #[case("65534", true)]
+    #[case("0", false)]
+    #[case("65535", false)]
+    fn test_inline_list_size(#[case] size: &str, #[case] ok: bool) {
+        let cli = Cli::try_parse_from(["cargo", "selector", "-n", size]);
+        assert_eq!(cli.is_ok(), ok);
+    }
+
+    #[rstest]
+    #[case(None, vec![], Ok(vec![]))]
+    #[case(
+        Some("--release -- --name \"Hello World\""),
+        vec![],
+        Ok(vec!["--release", "--", "--name", "Hello World"]),
+    )]
+    #[case(None, vec!["--name", "Hello World"], Ok(vec!["--", "--name", "Hello World"]))]
+    #[case(
+        Some("--release"),
+        vec!["--name", "Hello World"],
+        Ok(vec!["--release", "--", "--name", "Hello World"]),
+    )]
+    #[case(
+        Some("-- -v"),
+        vec!["--name", "Hello World"],
+        Ok(vec!["--", "-v", "--name", "Hello World"]),
+    )]
+    #[case(Some("-- --name 'Hello"), vec![], Err("unterminated single quote"))]
+    fn test_build_additional_args(
+        #[case] additional_args: Option<&str>,
+        #[case] args: Vec<&str>,
+        #[case] expected: Result<Vec<&str>, &str>,
+    ) {
+        let additional_args = additional_args.map(Into::into);
+        let args = args.into_iter().map(Into::into).collect();
+        let expected = expected
+            .map(|v| v.into_iter().map(Into::into).collect())
+            .map_err(Into::into);
+        assert_eq!(build_additional_args(additional_args, args), expected);
+    }
+
+    #[rstest]
+    #[case(MatchType::Substring, MatchType::Fuzzy)]
+    #[case(MatchType::Fuzzy, MatchType::Regex)]
+    #[case(MatchType::Exact, MatchType::Substring)]
+    fn test_match_type_next(#[case] match_type: MatchType, #[case] expected: MatchType) {
+        assert_eq!(match_type.next(), expected);
+    }
+
+    #[test]
+    fn test_action_next_from_custom() {
+        let flame = custom_action("flame", vec![]);
+        let watch = custom_action("watch", vec![TargetKind::Bin]);
+        let actions = Action::all(vec![flame.clone(), watch.clone()]);
+        let action = Action::Custom(flame);
+        let next = action.next(&actions, Some(TargetKind::Bin));
+        assert_eq!(next, Action::Custom(watch));
+        let next = action.next(&actions, Some(TargetKind::Test));
+        assert_eq!(next, Action::Run);
+    }
+
+    #[rstest]
+    #[case("(", true, false, Err("invalid query: "))]
+    #[case("(", false, true, Err("invalid query: "))]
+    #[case("(", false, false, Ok(None))]
+    #[case("^ser", false, true, Ok(Some("server")))]
+    #[case("^ser", true, false, Ok(None))]
+    #[case("^x", true, false, Err("no target matched"))]
+    #[case("^x", false, true, Ok(None))]
+    #[case("e", false, true, Ok(None))]
+    fn test_preselect(
+        #[case] query: &str,
+        #[case] exit_0: bool,
+        #[case] select_1: bool,
+        #[case] expected: Result<Option<&str>, &str>,
+    ) {
+        let targets = vec![
+            Target::fixture("server", TargetKind::Bin, "src/main.rs"),
+            Target::fixture("client", TargetKind::Bin, "src/bin/client.rs"),
+        ];
+        let mut tui = Tui::new(
+            targets,
+            Action::all(vec![]),
+            vec![cargo::DEV_PROFILE.into()],
+            state::WorkspaceState::default(),
+            MatchType::Regex,
+            Case::Smart,
+            &config::ColumnsConfig::default(),
+            config::ColorTheme::default(),
+        );
+        tui.set_query(query);
+        let actual = preselect(&tui, exit_0, select_1).map(|ret| {
+            ret.map(|ret| match ret {
+                Ret::Selected(t, _, _) => t.name,
+                _ => panic!("unexpected selection"),
+            })
+        });
+        match expected {
+            Ok(name) => assert_eq!(actual, Ok(name.map(String::from))),
+            Err(prefix) => assert!(actual.unwrap_err().starts_with(prefix)),
+        }
+    }
+}
@@ -1,10 +1,156 @@
+use std::ops::Range;
+
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use regex::{Regex, RegexBuilder};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::Case;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    Package,
+    Path,
+}
+
+// `pkg:server` or `path:examples/` narrows the match to the field
+fn parse_field(query: &str) -> (Option<Field>, &str) {
+    let prefixes = [
+        ("name:", Field::Name),
+        ("pkg:", Field::Package),
+        ("package:", Field::Package),
+        ("path:", Field::Path),
+    ];
+    prefixes
+        .into_iter()
+        .find_map(|(prefix, field)| query.strip_prefix(prefix).map(|q| (Some(field), q)))
+        .unwrap_or((None, query))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TermKind {
+    // matched with the base matcher (substring or fuzzy)
+    Base,
+    // 'term
+    Exact,
+    // ^term
+    Prefix,
+    // term$
+    Suffix,
+    // ^term$
+    Equal,
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    pattern: String,
+    kind: TermKind,
+    field: Option<Field>,
+    // !term
+    negated: bool,
+    // set by `Matcher::parse_query` according to the case mode
+    ignore_case: bool,
+    // compiled `pattern`, only for base terms of the regex matcher
+    regex: Option<Regex>,
+}
+
+impl PartialEq for Term {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+            && self.kind == other.kind
+            && self.field == other.field
+            && self.negated == other.negated
+            && self.ignore_case == other.ignore_case
+    }
+}
+
+impl Eq for Term {}
+
+impl Term {
+    // `anchors` is false if `^` and `$` are a part of the pattern, like in regex
+    fn parse(token: &str, anchors: bool) -> Option<Term> {
+        let (negated, token) = match token.strip_prefix('!') {
+            Some(t) => (true, t),
+            None => (false, token),
+        };
+        let (field, token) = parse_field(token);
+        let (kind, pattern) = if let Some(t) = token.strip_prefix('\'') {
+            (TermKind::Exact, t)
+        } else if let Some(t) = token.strip_prefix('^').filter(|_| anchors) {
+            match t.strip_suffix('$') {
+                Some(t) => (TermKind::Equal, t),
+                None => (TermKind::Prefix, t),
+            }
+        } else if let Some(t) = token.strip_suffix('$').filter(|_| anchors) {
+            (TermKind::Suffix, t)
+        } else {
+            (TermKind::Base, token)
+        };
+        if pattern.is_empty() {
+            return None;
+        }
+        // like fzf, a negated term is matched exactly (except for regex)
+        let kind = match kind {
+            TermKind::Base if negated && anchors => TermKind::Exact,
+            kind => kind,
+        };
+        Some(Term {
+            pattern: pattern.into(),
+            kind,
+            field,
+            negated,
+            ignore_case: false,
+            regex: None,
+        })
+    }
+}
+
+// fzf-style extended search syntax:
+// space-separated terms are combined with AND, and terms around `|` with OR
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Query {
+    groups: Vec<Vec<Term>>,
+}
+
+impl Query {
+    fn parse(s: &str, anchors: bool) -> Query {
+        let mut groups: Vec<Vec<Term>> = Vec::new();
+        let mut or_next = false;
+        for token in s.split_whitespace() {
+            if token == "|" {
+                or_next = !groups.is_empty();
+                continue;
+            }
+            let Some(term) = Term::parse(token, anchors) else {
+                continue;
+            };
+            match groups.last_mut() {
+                Some(group) if or_next => group.push(term),
+                _ => groups.push(vec![term]),
+            }
+            or_next = false;
+        }
+        Query { groups }
+    }
+}
+
+// matched char indices of each field, empty if the field did not match
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TargetMatch {
+    pub score: i64,
+    pub name: Vec<usize>,
+    pub package: Vec<usize>,
+    pub path: Vec<usize>,
+}
 
 #[derive(Default)]
 pub enum Matcher {
     #[default]
     Substring,
     Fuzzy(Box<SkimMatcherV2>),
+    Regex,
+    Prefix,
+    Exact,
 }
 
 impl Matcher {
@@ -13,23 +159,363 @@ impl Matcher {
     }
 
     pub fn fuzzy() -> Self {
-        Matcher::Fuzzy(Box::default())
+        // the case is folded by ourselves, since skim ignores only the ascii case
+        Matcher::Fuzzy(Box::new(SkimMatcherV2::default().respect_case()))
+    }
+
+    pub fn regex() -> Self {
+        Matcher::Regex
     }
 
-    pub fn match_indices(&self, text: &str, pattern: &str) -> Option<Vec<usize>> {
+    pub fn prefix() -> Self {
+        Matcher::Prefix
+    }
+
+    pub fn exact() -> Self {
+        Matcher::Exact
+    }
+
+    // returns the score and the matched char indices.
+    // for the regex matcher, `pattern` is compiled on every call and ignored if it is invalid.
+    // use `parse_query` to compile it once and get the error.
+    fn match_pattern(
+        &self,
+        text: &str,
+        pattern: &str,
+        ignore_case: bool,
+    ) -> Option<(i64, Vec<usize>)> {
         match self {
-            Matcher::Substring => text
-                .find(pattern)
-                .map(|pos| (pos..pos + pattern.len()).collect()),
-            Matcher::Fuzzy(matcher) => matcher
-                .fuzzy_indices(text, pattern)
-                .map(|(_, indices)| indices),
+            Matcher::Substring => substring_match(text, pattern, ignore_case),
+            // folding keeps the char indices returned by skim
+            Matcher::Fuzzy(matcher) if ignore_case => {
+                matcher.fuzzy_indices(&fold_str(text), &fold_str(pattern))
+            }
+            Matcher::Fuzzy(matcher) => matcher.fuzzy_indices(text, pattern),
+            Matcher::Regex => build_regex(pattern, ignore_case)
+                .ok()
+                .and_then(|re| regex_match(&re, text)),
+            Matcher::Prefix => prefix_match(text, pattern, ignore_case),
+            Matcher::Exact => equal_match(text, pattern, ignore_case),
+        }
+    }
+
+    // the matched char indices of a case-sensitive match
+    #[cfg(test)]
+    fn match_indices(&self, text: &str, pattern: &str) -> Option<Vec<usize>> {
+        self.match_pattern(text, pattern, false)
+            .map(|(_, indices)| indices)
+    }
+
+    // parses `s` and compiles the patterns which need it
+    pub fn parse_query(&self, s: &str, case: Case) -> Result<Query, String> {
+        let is_regex = matches!(self, Matcher::Regex);
+        let mut query = Query::parse(s, !is_regex);
+        for term in query.groups.iter_mut().flatten() {
+            term.ignore_case = ignores_case(case, &term.pattern, is_regex);
+            if is_regex && term.kind == TermKind::Base {
+                let re =
+                    build_regex(&term.pattern, term.ignore_case).map_err(regex_error_message)?;
+                term.regex = Some(re);
+            }
         }
+        Ok(query)
+    }
+}
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_START: i64 = 32;
+const BONUS_BOUNDARY: i64 = 16;
+const BONUS_WHOLE: i64 = 32;
+
+// score of a contiguous match of `text[start..end]`.
+// longer matches, earlier matches, and matches at the start of the text or a word rank higher.
+fn range_score(text: &str, start: usize, end: usize) -> i64 {
+    let len = text[start..end].chars().count() as i64;
+    let offset = text[..start].chars().count() as i64;
+    let mut score = SCORE_MATCH * len - offset;
+    if start == 0 {
+        score += BONUS_START;
+    } else if is_word_boundary(text, start) {
+        score += BONUS_BOUNDARY;
+    }
+    if start == 0 && end == text.len() {
+        score += BONUS_WHOLE;
+    }
+    score
+}
+
+// e.g. `foo_bar`, `foo-bar`, `foo/bar`, `fooBar`
+fn is_word_boundary(text: &str, pos: usize) -> bool {
+    let prev = text[..pos].chars().next_back();
+    let cur = text[pos..].chars().next();
+    match (prev, cur) {
+        (Some(p), Some(c)) => {
+            (!p.is_alphanumeric() && c.is_alphanumeric()) || (p.is_lowercase() && c.is_uppercase())
+        }
+        _ => false,
+    }
+}
+
+// `start` and `end` are byte offsets, but the returned indices are char indices
+fn range_match(text: &str, start: usize, end: usize) -> (i64, Vec<usize>) {
+    let char_start = text[..start].chars().count();
+    let char_end = char_start + text[start..end].chars().count();
+    (
+        range_score(text, start, end),
+        (char_start..char_end).collect(),
+    )
+}
+
+// extends the char indices to the whole grapheme clusters they belong to,
+// so that e.g. a combining mark is always highlighted with its base char
+fn expand_to_graphemes(text: &str, indices: Vec<usize>) -> Vec<usize> {
+    if text.is_ascii() || indices.is_empty() {
+        return indices;
+    }
+    let mut ret = Vec::with_capacity(indices.len());
+    let mut start = 0;
+    for g in text.graphemes(true) {
+        let end = start + g.chars().count();
+        if indices.iter().any(|i| (start..end).contains(i)) {
+            ret.extend(start..end);
+        }
+        start = end;
+    }
+    ret
+}
+
+// smart case ignores the case unless the pattern contains an uppercase char.
+// for regex, escapes like `\W` are not counted.
+fn ignores_case(case: Case, pattern: &str, is_regex: bool) -> bool {
+    match case {
+        Case::Sensitive => false,
+        Case::Insensitive => true,
+        Case::Smart => {
+            let mut escaped = false;
+            !pattern.chars().any(|c| {
+                let upper = c.is_uppercase() && !escaped;
+                escaped = is_regex && c == '\\' && !escaped;
+                upper
+            })
+        }
+    }
+}
+
+// lowercase of `c` if it is a single char, so that the char indices are kept
+fn fold_char(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+fn fold_str(s: &str) -> String {
+    s.chars().map(fold_char).collect()
+}
+
+// `text` with each char folded, and the byte offset in the original text of each byte.
+// the folded chars can have a different length in bytes, like `K` (kelvin sign) and `k`.
+struct Folded {
+    text: String,
+    offsets: Vec<usize>,
+}
+
+impl Folded {
+    fn new(text: &str) -> Folded {
+        let mut folded = String::with_capacity(text.len());
+        let mut offsets = Vec::with_capacity(text.len() + 1);
+        for (i, c) in text.char_indices() {
+            folded.push(fold_char(c));
+            offsets.resize(folded.len(), i);
+        }
+        offsets.push(text.len());
+        Folded {
+            text: folded,
+            offsets,
+        }
+    }
+
+    fn original_range(&self, range: Range<usize>) -> Range<usize> {
+        self.offsets[range.start]..self.offsets[range.end]
+    }
+}
+
+// byte ranges found by `find(text, pattern)`, on the folded text if `ignore_case`
+fn find_ranges<F>(text: &str, pattern: &str, ignore_case: bool, find: F) -> Vec<Range<usize>>
+where
+    F: Fn(&str, &str) -> Vec<Range<usize>>,
+{
+    if !ignore_case {
+        return find(text, pattern);
+    }
+    let folded = Folded::new(text);
+    find(&folded.text, &fold_str(pattern))
+        .into_iter()
+        .map(|r| folded.original_range(r))
+        .collect()
+}
+
+// the best scored range, the first one if tied
+fn best_match(text: &str, ranges: Vec<Range<usize>>) -> Option<(i64, Vec<usize>)> {
+    ranges
+        .into_iter()
+        .map(|r| range_match(text, r.start, r.end))
+        .reduce(|best, m| if m.0 > best.0 { m } else { best })
+}
+
+fn substring_match(text: &str, pattern: &str, ignore_case: bool) -> Option<(i64, Vec<usize>)> {
+    let ranges = find_ranges(text, pattern, ignore_case, |t, p| {
+        t.match_indices(p).map(|(i, m)| i..i + m.len()).collect()
+    });
+    best_match(text, ranges)
+}
+
+fn prefix_match(text: &str, pattern: &str, ignore_case: bool) -> Option<(i64, Vec<usize>)> {
+    let ranges = find_ranges(text, pattern, ignore_case, |t, p| {
+        t.starts_with(p).then_some(0..p.len()).into_iter().collect()
+    });
+    best_match(text, ranges)
+}
+
+fn suffix_match(text: &str, pattern: &str, ignore_case: bool) -> Option<(i64, Vec<usize>)> {
+    let ranges = find_ranges(text, pattern, ignore_case, |t, p| {
+        t.ends_with(p)
+            .then(|| t.len() - p.len()..t.len())
+            .into_iter()
+            .collect()
+    });
+    best_match(text, ranges)
+}
+
+fn equal_match(text: &str, pattern: &str, ignore_case: bool) -> Option<(i64, Vec<usize>)> {
+    let ranges = find_ranges(text, pattern, ignore_case, |t, p| {
+        (t == p).then_some(0..t.len()).into_iter().collect()
+    });
+    best_match(text, ranges)
+}
+
+fn build_regex(pattern: &str, ignore_case: bool) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(ignore_case)
+        .build()
+}
+
+fn regex_match(re: &Regex, text: &str) -> Option<(i64, Vec<usize>)> {
+    re.find(text).map(|m| range_match(text, m.start(), m.end()))
+}
+
+// the last line of the error, without the pattern and position which are already shown in the input
+fn regex_error_message(e: regex::Error) -> String {
+    let msg = e.to_string();
+    let line = msg.lines().last().unwrap_or_default();
+    let line = line.strip_prefix("error: ").unwrap_or(line);
+    format!("invalid regex: {line}")
+}
+
+impl Matcher {
+    fn match_term(&self, text: &str, term: &Term) -> Option<(i64, Vec<usize>)> {
+        let p = term.pattern.as_str();
+        let ic = term.ignore_case;
+        let m = match term.kind {
+            TermKind::Base => match &term.regex {
+                Some(re) => regex_match(re, text),
+                None => self.match_pattern(text, p, ic),
+            },
+            TermKind::Exact => substring_match(text, p, ic),
+            TermKind::Prefix => prefix_match(text, p, ic),
+            TermKind::Suffix => suffix_match(text, p, ic),
+            TermKind::Equal => equal_match(text, p, ic),
+        };
+        m.map(|(score, indices)| (score, expand_to_graphemes(text, indices)))
+    }
+
+    // `fields` are the texts to be matched, with the field they belong to.
+    // a term without a field matches any of them, and a text without a field matches any term.
+    // returns the score and the merged indices of the positive terms for each text.
+    // the score is the sum of the best score of each term, where matches in the name count most.
+    fn match_fields<const N: usize>(
+        &self,
+        fields: [(Option<Field>, &str); N],
+        query: &Query,
+    ) -> Option<(i64, [Vec<usize>; N])> {
+        let mut score = 0;
+        let mut indices: [Vec<usize>; N] = std::array::from_fn(|_| Vec::new());
+        for group in &query.groups {
+            let mut group_matched = false;
+            let mut group_score = 0;
+            for term in group {
+                let mut term_matched = false;
+                let mut term_score = i64::MIN;
+                let mut term_indices: [Vec<usize>; N] = std::array::from_fn(|_| Vec::new());
+                for (i, (field, text)) in fields.iter().enumerate() {
+                    if field.is_some() && term.field.is_some() && *field != term.field {
+                        continue;
+                    }
+                    if let Some((s, is)) = self.match_term(text, term) {
+                        let s = match field {
+                            None | Some(Field::Name) => s,
+                            Some(_) => s / 2,
+                        };
+                        term_matched = true;
+                        term_score = term_score.max(s);
+                        term_indices[i] = is;
+                    }
+                }
+                if term.negated {
+                    group_matched |= !term_matched;
+                } else if term_matched {
+                    group_matched = true;
+                    group_score = group_score.max(term_score);
+                    for (acc, is) in indices.iter_mut().zip(term_indices) {
+                        acc.extend(is);
+                    }
+                }
+            }
+            if !group_matched {
+                return None;
+            }
+            score += group_score;
+        }
+        for i in indices.iter_mut() {
+            i.sort_unstable();
+            i.dedup();
+        }
+        Some((score, indices))
+    }
+
+    pub fn match_query(&self, text: &str, query: &Query) -> Option<(i64, Vec<usize>)> {
+        self.match_fields([(None, text)], query)
+            .map(|(score, [indices])| (score, indices))
+    }
+
+    // matches if all the terms match any of the fields (or the field specified in the term)
+    pub fn match_target(
+        &self,
+        name: &str,
+        package: &str,
+        path: &str,
+        query: &Query,
+    ) -> Option<TargetMatch> {
+        let fields = [
+            (Some(Field::Name), name),
+            (Some(Field::Package), package),
+            (Some(Field::Path), path),
+        ];
+        self.match_fields(fields, query)
+            .map(|(score, [name, package, path])| TargetMatch {
+                score,
+                name,
+                package,
+                path,
+            })
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use rstest::rstest;
+
     use super::*;
 
     #[test]
@@ -49,4 +535,324 @@ mod tests {
         assert_eq!(matcher.match_indices("hello", "ho"), Some(vec![0, 4]));
         assert_eq!(matcher.match_indices("hello", "wr"), None);
     }
+
+    #[rstest]
+    #[case("server", (None, "server"))]
+    #[case("pkg:server", (Some(Field::Package), "server"))]
+    #[case("package:server", (Some(Field::Package), "server"))]
+    #[case("path:examples/", (Some(Field::Path), "examples/"))]
+    #[case("name:", (Some(Field::Name), ""))]
+    #[case("foo:bar", (None, "foo:bar"))]
+    fn test_parse_field(#[case] query: &str, #[case] expected: (Option<Field>, &str)) {
+        assert_eq!(parse_field(query), expected);
+    }
+
+    fn target_match(name: Vec<usize>, package: Vec<usize>, path: Vec<usize>) -> TargetMatch {
+        TargetMatch {
+            score: 0,
+            name,
+            package,
+            path,
+        }
+    }
+
+    #[rstest]
+    #[case("", Some(target_match(vec![], vec![], vec![])))]
+    #[case("main", Some(target_match(vec![0, 1, 2, 3], vec![], vec![4, 5, 6, 7])))]
+    #[case("server", Some(target_match(vec![], vec![0, 1, 2, 3, 4, 5], vec![])))]
+    #[case("src", Some(target_match(vec![], vec![], vec![0, 1, 2])))]
+    #[case("pkg:serv", Some(target_match(vec![], vec![0, 1, 2, 3], vec![])))]
+    #[case("pkg:main", None)]
+    #[case("name:src", None)]
+    #[case("path:main", Some(target_match(vec![], vec![], vec![4, 5, 6, 7])))]
+    #[case("client", None)]
+    #[case("pkg:serv main", Some(target_match(vec![0, 1, 2, 3], vec![0, 1, 2, 3], vec![4, 5, 6, 7])))]
+    #[case("pkg:serv !path:test", Some(target_match(vec![], vec![0, 1, 2, 3], vec![])))]
+    #[case("pkg:serv !name:main", None)]
+    #[case("pkg:client | name:^ma", Some(target_match(vec![0, 1], vec![], vec![])))]
+    fn test_match_target(#[case] query: &str, #[case] expected: Option<TargetMatch>) {
+        let matcher = Matcher::substring();
+        let query = matcher.parse_query(query, Case::Smart).unwrap();
+        let actual = matcher.match_target("main", "server", "src/main.rs", &query);
+        assert_eq!(actual.map(|m| TargetMatch { score: 0, ..m }), expected);
+    }
+
+    fn term(pattern: &str, kind: TermKind, field: Option<Field>, negated: bool) -> Term {
+        Term {
+            pattern: pattern.into(),
+            kind,
+            field,
+            negated,
+            ignore_case: false,
+            regex: None,
+        }
+    }
+
+    #[rstest]
+    #[case("", vec![])]
+    #[case("foo", vec![vec![term("foo", TermKind::Base, None, false)]])]
+    #[case("'foo", vec![vec![term("foo", TermKind::Exact, None, false)]])]
+    #[case("^foo", vec![vec![term("foo", TermKind::Prefix, None, false)]])]
+    #[case("foo$", vec![vec![term("foo", TermKind::Suffix, None, false)]])]
+    #[case("^foo$", vec![vec![term("foo", TermKind::Equal, None, false)]])]
+    #[case("!foo", vec![vec![term("foo", TermKind::Exact, None, true)]])]
+    #[case("!^foo", vec![vec![term("foo", TermKind::Prefix, None, true)]])]
+    #[case("!pkg:'foo", vec![vec![term("foo", TermKind::Exact, Some(Field::Package), true)]])]
+    #[case(
+        "  foo   bar ",
+        vec![
+            vec![term("foo", TermKind::Base, None, false)],
+            vec![term("bar", TermKind::Base, None, false)],
+        ],
+    )]
+    #[case(
+        "foo | bar$ baz",
+        vec![
+            vec![
+                term("foo", TermKind::Base, None, false),
+                term("bar", TermKind::Suffix, None, false),
+            ],
+            vec![term("baz", TermKind::Base, None, false)],
+        ],
+    )]
+    #[case("| foo |", vec![vec![term("foo", TermKind::Base, None, false)]])]
+    #[case("^ ! $ '", vec![])]
+    fn test_query_parse(#[case] s: &str, #[case] expected: Vec<Vec<Term>>) {
+        assert_eq!(Query::parse(s, true), Query { groups: expected });
+    }
+
+    #[rstest]
+    #[case("", Some(vec![]))]
+    #[case("hel", Some(vec![0, 1, 2]))]
+    #[case("hel wor", Some(vec![0, 1, 2, 6, 7, 8]))]
+    #[case("hel xyz", None)]
+    #[case("xyz | wor", Some(vec![6, 7, 8]))]
+    #[case("hel | llo", Some(vec![0, 1, 2, 3, 4]))]
+    #[case("^hel", Some(vec![0, 1, 2]))]
+    #[case("^wor", None)]
+    #[case("rld$", Some(vec![8, 9, 10]))]
+    #[case("hel$", None)]
+    #[case("^hello_world$", Some((0..11).collect()))]
+    #[case("^hello$", None)]
+    #[case("!xyz", Some(vec![]))]
+    #[case("!hel", None)]
+    #[case("hel !^wor", Some(vec![0, 1, 2]))]
+    #[case("hw", None)]
+    #[case("'hw", None)]
+    fn test_match_query_substring(#[case] query: &str, #[case] expected: Option<Vec<usize>>) {
+        let matcher = Matcher::substring();
+        let query = matcher.parse_query(query, Case::Smart).unwrap();
+        let actual = matcher
+            .match_query("hello_world", &query)
+            .map(|(_, indices)| indices);
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case("hw", Some(vec![0, 6]))]
+    #[case("'hw", None)]
+    #[case("'lo_w", Some(vec![3, 4, 5, 6]))]
+    #[case("hw !xyz", Some(vec![0, 6]))]
+    #[case("!hw", Some(vec![]))]
+    #[case("hw ^hel", Some(vec![0, 1, 2, 6]))]
+    fn test_match_query_fuzzy(#[case] query: &str, #[case] expected: Option<Vec<usize>>) {
+        let matcher = Matcher::fuzzy();
+        let query = matcher.parse_query(query, Case::Smart).unwrap();
+        let actual = matcher
+            .match_query("hello_world", &query)
+            .map(|(_, indices)| indices);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_matcher_regex() {
+        let matcher = Matcher::regex();
+        assert_eq!(
+            matcher
+                .match_pattern("hello", "^h.l", false)
+                .map(|(_, i)| i),
+            Some(vec![0, 1, 2])
+        );
+        assert_eq!(
+            matcher
+                .match_pattern("hello", "l+o$", false)
+                .map(|(_, i)| i),
+            Some(vec![2, 3, 4])
+        );
+        assert_eq!(
+            matcher.match_pattern("hello", "h.o", false).map(|(_, i)| i),
+            None
+        );
+        assert_eq!(
+            matcher.match_pattern("hello", "(he", false).map(|(_, i)| i),
+            None
+        );
+    }
+
+    #[test]
+    fn test_matcher_prefix() {
+        let matcher = Matcher::prefix();
+        assert_eq!(
+            matcher.match_pattern("hello", "he", false).map(|(_, i)| i),
+            Some(vec![0, 1])
+        );
+        assert_eq!(
+            matcher.match_pattern("hello", "lo", false).map(|(_, i)| i),
+            None
+        );
+        assert_eq!(
+            matcher.match_pattern("hello", "", false).map(|(_, i)| i),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn test_matcher_exact() {
+        let matcher = Matcher::exact();
+        assert_eq!(
+            matcher
+                .match_pattern("hello", "hello", false)
+                .map(|(_, i)| i),
+            Some(vec![0, 1, 2, 3, 4])
+        );
+        assert_eq!(
+            matcher
+                .match_pattern("hello", "hell", false)
+                .map(|(_, i)| i),
+            None
+        );
+    }
+
+    #[rstest]
+    #[case("^ser.*r$", Some(vec![0, 1, 2, 3, 4, 5]))]
+    #[case("ser.*r$ !^c", Some(vec![0, 1, 2, 3, 4, 5]))]
+    #[case("ser.*r$ !^s", None)]
+    #[case("e[rv] 'ver", Some(vec![1, 2, 3, 4, 5]))]
+    #[case("^cli | v.r", Some(vec![3, 4, 5]))]
+    #[case("x+", None)]
+    fn test_match_query_regex(#[case] query: &str, #[case] expected: Option<Vec<usize>>) {
+        let matcher = Matcher::regex();
+        let query = matcher.parse_query(query, Case::Smart).unwrap();
+        let actual = matcher
+            .match_query("server", &query)
+            .map(|(_, indices)| indices);
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(Case::Smart, "foo", false, true)]
+    #[case(Case::Smart, "Foo", false, false)]
+    #[case(Case::Smart, "Ärger", false, false)]
+    #[case(Case::Smart, "\\Wfoo", true, true)]
+    #[case(Case::Smart, "\\Wfoo", false, false)]
+    #[case(Case::Sensitive, "foo", false, false)]
+    #[case(Case::Insensitive, "Foo", false, true)]
+    fn test_ignores_case(
+        #[case] case: Case,
+        #[case] pattern: &str,
+        #[case] is_regex: bool,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(ignores_case(case, pattern, is_regex), expected);
+    }
+
+    #[rstest]
+    #[case(Matcher::substring(), Case::Smart, "server", "MyServer", Some(vec![2, 3, 4, 5, 6, 7]))]
+    #[case(Matcher::substring(), Case::Smart, "Server", "my_server", None)]
+    #[case(Matcher::substring(), Case::Sensitive, "server", "MyServer", None)]
+    #[case(Matcher::substring(), Case::Insensitive, "Server", "my_server", Some(vec![3, 4, 5, 6, 7, 8]))]
+    #[case(Matcher::substring(), Case::Smart, "ärger", "ÄRGER", Some((0..5).collect()))]
+    // the kelvin sign is 3 bytes, and its lowercase `k` is 1 byte
+    #[case(Matcher::substring(), Case::Smart, "ok", "\u{212a}OK", Some(vec![1, 2]))]
+    #[case(Matcher::substring(), Case::Smart, "k", "\u{212a}", Some(vec![0]))]
+    #[case(Matcher::substring(), Case::Smart, "^σ", "ΣΑΣ", Some(vec![0]))]
+    #[case(Matcher::substring(), Case::Smart, "σ$", "ΣΑΣ", Some(vec![2]))]
+    #[case(Matcher::substring(), Case::Smart, "^σασ$", "ΣΑΣ", Some((0..3).collect()))]
+    #[case(Matcher::fuzzy(), Case::Smart, "ms", "MyServer", Some(vec![0, 2]))]
+    #[case(Matcher::fuzzy(), Case::Smart, "Ms", "my_server", None)]
+    #[case(Matcher::fuzzy(), Case::Smart, "äg", "ÄRGER", Some(vec![0, 2]))]
+    #[case(Matcher::regex(), Case::Smart, "s.rv", "MyServer", Some(vec![2, 3, 4, 5]))]
+    #[case(Matcher::regex(), Case::Smart, "S.rv", "my_server", None)]
+    #[case(Matcher::regex(), Case::Smart, "är", "ÄRGER", Some(vec![0, 1]))]
+    #[case(Matcher::prefix(), Case::Smart, "my", "MyServer", Some(vec![0, 1]))]
+    #[case(Matcher::exact(), Case::Smart, "myserver", "MyServer", Some((0..8).collect()))]
+    fn test_match_query_case(
+        #[case] matcher: Matcher,
+        #[case] case: Case,
+        #[case] query: &str,
+        #[case] text: &str,
+        #[case] expected: Option<Vec<usize>>,
+    ) {
+        let query = matcher.parse_query(query, case).unwrap();
+        let actual = matcher
+            .match_query(text, &query)
+            .map(|(_, indices)| indices);
+        assert_eq!(actual, expected);
+    }
+
+    // indices are char indices, extended to whole grapheme clusters
+    #[rstest]
+    #[case(Matcher::substring(), "日本", "こんにちは日本語", Some(vec![5, 6]))]
+    #[case(Matcher::substring(), "語", "日本語_cli", Some(vec![2]))]
+    #[case(Matcher::substring(), "cli", "日本語_cli", Some(vec![4, 5, 6]))]
+    #[case(Matcher::fuzzy(), "日語", "日本語_cli", Some(vec![0, 2]))]
+    #[case(Matcher::regex(), "本.", "日本語_cli", Some(vec![1, 2]))]
+    #[case(Matcher::prefix(), "日本", "日本語", Some(vec![0, 1]))]
+    // `e` followed by a combining acute accent
+    #[case(Matcher::substring(), "cafe", "cafe\u{301}_app", Some(vec![0, 1, 2, 3, 4]))]
+    #[case(Matcher::substring(), "app", "cafe\u{301}_app", Some(vec![6, 7, 8]))]
+    #[case(Matcher::substring(), "\u{301}", "cafe\u{301}", Some(vec![3, 4]))]
+    #[case(Matcher::fuzzy(), "e_", "cafe\u{301}_app", Some(vec![3, 4, 5]))]
+    #[case(Matcher::substring(), "^cafe$", "cafe\u{301}", None)]
+    fn test_match_query_unicode(
+        #[case] matcher: Matcher,
+        #[case] query: &str,
+        #[case] text: &str,
+        #[case] expected: Option<Vec<usize>>,
+    ) {
+        let query = matcher.parse_query(query, Case::Smart).unwrap();
+        let actual = matcher
+            .match_query(text, &query)
+            .map(|(_, indices)| indices);
+        assert_eq!(actual, expected);
+    }
+
+    // candidates in descending order of score
+    #[rstest]
+    #[case(Matcher::substring(), "main", &["main", "main_test", "server_main", "domain", "example/remain"])]
+    #[case(Matcher::substring(), "run", &["runner", "cli_run", "main_run", "rerun"])]
+    #[case(Matcher::fuzzy(), "srv", &["srv", "server", "sub_rev"])]
+    #[case(Matcher::regex(), "ma.n", &["main", "cli_main", "domain"])]
+    fn test_match_query_score(
+        #[case] matcher: Matcher,
+        #[case] query: &str,
+        #[case] candidates: &[&str],
+    ) {
+        let query = matcher.parse_query(query, Case::Smart).unwrap();
+        let scores: Vec<i64> = candidates
+            .iter()
+            .map(|c| matcher.match_query(c, &query).unwrap().0)
+            .collect();
+        assert!(scores.windows(2).all(|w| w[0] > w[1]), "{scores:?}");
+    }
+
+    #[test]
+    fn test_match_target_score() {
+        let matcher = Matcher::substring();
+        let query = matcher.parse_query("server", Case::Smart).unwrap();
+        let in_name = matcher.match_target("server", "app", "src/bin/server.rs", &query);
+        let in_package = matcher.match_target("cli", "server", "src/bin/cli.rs", &query);
+        assert!(in_name.unwrap().score > in_package.unwrap().score);
+    }
+
+    #[rstest]
+    #[case("(foo", "invalid regex: unclosed group")]
+    #[case("ok [a-", "invalid regex: unclosed character class")]
+    fn test_parse_query_regex_error(#[case] query: &str, #[case] expected: &str) {
+        let matcher = Matcher::regex();
+        assert_eq!(
+            matcher.parse_query(query, Case::Smart),
+            Err(expected.into())
+        );
+    }
 }
@@ -0,0 +1,76 @@
+// the source of the selected target shown next to or below the list.
+// the source is shown from the crate-level doc comment if there is one, or from `fn main`.
+
+use std::fs;
+
+use serde::Deserialize;
+
+const TAB: &str = "    ";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewPosition {
+    #[default]
+    Right,
+    Bottom,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SourcePreview {
+    // 0-based index of the first line to show
+    pub start: usize,
+    pub lines: Vec<String>,
+}
+
+pub fn load(src_path: &str) -> Result<SourcePreview, String> {
+    let source = fs::read_to_string(src_path).map_err(|e| e.to_string())?;
+    Ok(SourcePreview {
+        start: start_line(&source),
+        lines: source.lines().map(|l| l.replace('\t', TAB)).collect(),
+    })
+}
+
+fn start_line(source: &str) -> usize {
+    let lines: Vec<&str> = source.lines().collect();
+    if let Some(i) = lines.iter().position(|l| l.trim_start().starts_with("//!")) {
+        return i;
+    }
+    let Some(i) = lines.iter().position(|l| is_main_fn(l)) else {
+        return 0;
+    };
+    // include the attributes like `#[tokio::main]`
+    let attrs = lines[..i]
+        .iter()
+        .rev()
+        .take_while(|l| l.trim_start().starts_with("#["))
+        .count();
+    i - attrs
+}
+
+fn is_main_fn(line: &str) -> bool {
+    let line = line.trim_start();
+    let line = line.strip_prefix("pub ").unwrap_or(line);
+    let line = line.strip_prefix("async ").unwrap_or(line);
+    line.strip_prefix("fn main")
+        .is_some_and(|rest| rest.trim_start().starts_with('('))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("", 0)]
+    #[case("use std::env;\n\nfn main() {\n}\n", 2)]
+    #[case("use std::env;\n\npub fn main () {}\n", 2)]
+    #[case("fn helper() {}\n#[tokio::main]\nasync fn main() {}\n", 1)]
+    #[case("fn main_loop() {}\n", 0)]
+    #[case("// fn main() {}\nfn run() {}\n", 0)]
+    #[case("// license\n//! Prints hello.\n//!\nfn main() {}\n", 1)]
+    #[case("pub mod a;\n\n    //! indented\n", 2)]
+    fn test_start_line(#[case] source: &str, #[case] expected: usize) {
+        assert_eq!(start_line(source), expected);
+    }
+}
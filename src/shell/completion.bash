@@ -0,0 +1,23 @@
+# completes `cargo selector ...` with the completions of `cargo-selector`,
+# and the other cargo commands with the original completion of cargo
+if ! complete -p cargo >/dev/null 2>&1; then
+  # bash-completion loads the completion of cargo lazily
+  if declare -F _comp_load >/dev/null; then
+    _comp_load cargo 2>/dev/null
+  elif declare -F _completion_loader >/dev/null; then
+    _completion_loader cargo 2>/dev/null
+  fi
+fi
+if [[ $(complete -p cargo 2>/dev/null) =~ -F\ ([^ ]+) && ${BASH_REMATCH[1]} != __cargo_selector_complete ]]; then
+  __cargo_selector_cargo_complete=${BASH_REMATCH[1]}
+fi
+__cargo_selector_complete() {
+  if [[ ${COMP_WORDS[1]} == selector ]] && ((COMP_CWORD > 1)); then
+    local COMP_WORDS=(cargo-selector "${COMP_WORDS[@]:2}")
+    local COMP_CWORD=$((COMP_CWORD - 1))
+    _cargo__selector cargo-selector "${COMP_WORDS[COMP_CWORD]}" "${COMP_WORDS[COMP_CWORD - 1]}"
+  elif [[ -n $__cargo_selector_cargo_complete ]]; then
+    "$__cargo_selector_cargo_complete" "$@"
+  fi
+}
+complete -F __cargo_selector_complete -o bashdefault -o default cargo
@@ -0,0 +1,6 @@
+# completes `cargo selector ...` with the completions of `cargo-selector`
+function __cargo_selector_complete
+    set -l args (commandline -opc)[3..-1]
+    complete -C (string join ' ' -- cargo-selector (string escape -- $args) (commandline -ct))
+end
+complete -c cargo -n '__fish_seen_subcommand_from selector' -f -a '(__cargo_selector_complete)'
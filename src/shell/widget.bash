@@ -0,0 +1,10 @@
+# cargo-selector key binding for bash
+# press Ctrl-X Ctrl-R to select a target and insert the command into the command line
+__cargo_selector_widget() {
+  local cmd
+  cmd="$(cargo selector --inline --print)" || return
+  READLINE_LINE="${READLINE_LINE:0:$READLINE_POINT}${cmd}${READLINE_LINE:$READLINE_POINT}"
+  READLINE_POINT=$((READLINE_POINT + ${#cmd}))
+}
+bind -m emacs-standard -x '"\C-x\C-r": __cargo_selector_widget'
+bind -m vi-insert -x '"\C-x\C-r": __cargo_selector_widget'
@@ -0,0 +1,11 @@
+# cargo-selector key binding for fish
+# press Ctrl-X Ctrl-R to select a target and insert the command into the command line
+function __cargo_selector_widget
+    set -l cmd (cargo selector --inline --print)
+    and commandline --insert -- $cmd
+    commandline --function repaint
+end
+bind \cx\cr __cargo_selector_widget
+if bind -M insert >/dev/null 2>&1
+    bind -M insert \cx\cr __cargo_selector_widget
+end
@@ -0,0 +1,82 @@
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::Target;
+
+const APP_DIR_NAME: &str = "cargo-selector";
+const STATE_FILE_NAME: &str = "state.toml";
+const MAX_ARGS_HISTORY_LEN: usize = 20;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceState {
+    pub profile: Option<String>,
+    // keyed by `target_key`, oldest first
+    #[serde(default)]
+    pub args_history: BTreeMap<String, Vec<String>>,
+}
+
+impl WorkspaceState {
+    pub fn args_history(&self, target: &Target) -> &[String] {
+        self.args_history
+            .get(&target_key(target))
+            .map(|h| h.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn push_args_history(&mut self, target: &Target, args: &str) {
+        let history = self.args_history.entry(target_key(target)).or_default();
+        history.retain(|a| a != args);
+        history.push(args.to_string());
+        if history.len() > MAX_ARGS_HISTORY_LEN {
+            history.remove(0);
+        }
+    }
+}
+
+pub fn target_key(target: &Target) -> String {
+    format!("{}/{}/{}", target.package, target.kind.name(), target.name)
+}
+
+pub fn data_file_path(file_name: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join(APP_DIR_NAME).join(file_name))
+}
+
+// a missing file is empty, but a file that cannot be read or parsed is an error,
+// so that it is not overwritten by the next save
+pub fn read_data_file<T: DeserializeOwned + Default>(file_name: &str) -> io::Result<T> {
+    let Some(path) = data_file_path(file_name) else {
+        return Ok(T::default());
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(T::default()),
+        Err(e) => return Err(e),
+    };
+    toml::from_str(&content)
+        .map_err(|e| io::Error::other(format!("failed to parse {}: {e}", path.display())))
+}
+
+fn load_all() -> io::Result<BTreeMap<String, WorkspaceState>> {
+    read_data_file(STATE_FILE_NAME)
+}
+
+pub fn load(workspace_root: &str) -> WorkspaceState {
+    load_all()
+        .ok()
+        .and_then(|mut all| all.remove(workspace_root))
+        .unwrap_or_default()
+}
+
+pub fn save(workspace_root: &str, state: WorkspaceState) -> io::Result<()> {
+    let Some(path) = data_file_path(STATE_FILE_NAME) else {
+        return Ok(());
+    };
+    let mut all = load_all()?;
+    all.insert(workspace_root.to_string(), state);
+    let content = toml::to_string(&all).map_err(io::Error::other)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)
+}
@@ -1,63 +1,205 @@
+use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
 use console::truncate_str;
-use laurier::highlight::highlight_matched_text;
 use ratatui::{
     backend::Backend,
-    crossterm::event::{self, Event},
+    crossterm::event::{self, Event, KeyEvent},
     layout::{Constraint, Layout, Rect},
-    style::{Style, Stylize},
-    text::{Line, Text},
-    widgets::{Block, List, ListItem, Paragraph},
+    style::{Color, Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, List, ListItem, Padding, Paragraph},
     Frame, Terminal,
 };
 use tui_input::{backend::crossterm::EventHandler, Input};
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
-    config::ColorTheme,
+    cargo,
+    config::{ColorTheme, ColumnsConfig, PreviewConfig},
     event::{UserEvent, UserEventMapper},
+    history::{self, HistoryEntry},
+    layout::{self, Align, Column, ColumnSpec, Truncate},
     matcher::Matcher,
-    util::digits,
-    Action, Target, TargetKind,
+    preview::{self, PreviewPosition, SourcePreview},
+    state::{target_key, WorkspaceState},
+    util::{digits, split_words},
+    Action, Case, ExecOptions, FeatureSelection, MatchType, Target, TargetKind,
 };
 
 const ELLIPSIS: &str = "..";
+const MIN_INPUT_W: usize = 10;
+const MIN_PREVIEW_W: usize = 20;
+const MIN_PREVIEW_H: usize = 3;
+
+const NO_DEFAULT_FEATURES: &str = "--no-default-features";
+const ALL_FEATURES: &str = "--all-features";
 
 #[derive(Default)]
 pub struct Tui {
     targets: Vec<Target>,
     filtered: Vec<FilteredTarget>,
+    rows: Vec<Row>,
+    // index of `rows`
     cursor: usize,
     input: Input,
     action: Action,
+    actions: Vec<Action>,
+    stage: Stage,
+    // set if the query is invalid, e.g. an invalid regex
+    query_error: Option<String>,
+    feature_selections: HashMap<String, FeatureSelection>,
+    profiles: Vec<String>,
+    profile: String,
+    state: WorkspaceState,
 
     list_height: usize,
     list_offset: usize,
 
     show_features: bool,
+    show_package: bool,
+    columns: Vec<ColumnSpec>,
+    // the last time each target was run, keyed by `state::target_key`
+    last_runs: HashMap<String, u64>,
+    // frecency of each target, keyed by `state::target_key`
+    frecency: HashMap<String, f64>,
+    // group the targets under the headers of their packages
+    grouped: bool,
+    collapsed: HashSet<String>,
+    show_preview: bool,
+    preview_position: PreviewPosition,
+    // the source of the selected target, keyed by its `src_path`
+    preview: Option<(String, Result<SourcePreview, String>)>,
 
+    match_type: MatchType,
+    case: Case,
     matcher: Matcher,
     mapper: UserEventMapper,
     theme: ColorTheme,
 }
 
+#[derive(Default)]
 struct FilteredTarget {
     index: usize,
+    score: i64,
+    // char indices, covering whole grapheme clusters
     match_indices: Vec<usize>,
+    // only for targets
+    package_match_indices: Vec<usize>,
+    path_match_indices: Vec<usize>,
+}
+
+// a row of the list.
+// only the target list has headers, and the rows of the other lists are their items as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Row {
+    // index of `filtered`
+    Item(usize),
+    Header {
+        package: String,
+        len: usize,
+        collapsed: bool,
+    },
+}
+
+impl Row {
+    // an expanded header is skipped by the cursor, and a collapsed one stands for its targets
+    fn is_selectable(&self) -> bool {
+        !matches!(
+            self,
+            Row::Header {
+                collapsed: false,
+                ..
+            }
+        )
+    }
+}
+
+#[derive(Default)]
+enum Stage {
+    #[default]
+    Targets,
+    Tests(Box<TestsStage>),
+    Features(Box<FeaturesStage>),
+    Args(Box<ArgsStage>),
+    History(Box<HistoryStage>),
+}
+
+struct TestsStage {
+    target: Target,
+    tests: Result<Vec<String>, String>,
+    prev_input: Input,
+}
+
+struct FeaturesStage {
+    target: Target,
+    // `--no-default-features` and `--all-features` followed by the package features
+    items: Vec<String>,
+    prev_input: Input,
+}
+
+struct ArgsStage {
+    target: Target,
+    input: Input,
+    // index of the args history shown in the input, `None` if editing a new one
+    history_index: Option<usize>,
+    error: Option<String>,
+}
+
+struct HistoryStage {
+    // newest first
+    entries: Vec<HistoryEntry>,
+    labels: Vec<String>,
+    now: u64,
 }
 
 pub enum Ret {
     Quit,
-    Selected(Target, Action),
+    Selected(Target, Action, ExecOptions),
+    SelectedTest(Target, String, ExecOptions),
+    SelectedHistory(HistoryEntry),
     NotSelected,
 }
 
 impl Tui {
-    pub fn new(targets: Vec<Target>, term_size: Rect, matcher: Matcher, theme: ColorTheme) -> Tui {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        targets: Vec<Target>,
+        actions: Vec<Action>,
+        profiles: Vec<String>,
+        state: WorkspaceState,
+        match_type: MatchType,
+        case: Case,
+        columns: &ColumnsConfig,
+        theme: ColorTheme,
+    ) -> Tui {
         let show_features = targets.iter().any(|t| !t.required_features.is_empty());
+        let show_package = targets
+            .iter()
+            .map(|t| t.package.as_str())
+            .collect::<HashSet<_>>()
+            .len()
+            > 1;
+        let profile = state
+            .profile
+            .clone()
+            .filter(|p| profiles.contains(p))
+            .unwrap_or(cargo::DEV_PROFILE.into());
         let mut tui = Tui {
             targets,
-            list_height: Tui::calc_list_height(term_size.height),
+            actions,
+            profiles,
+            profile,
+            state,
             show_features,
-            matcher,
+            show_package,
+            columns: layout::column_specs(columns),
+            match_type,
+            case,
+            matcher: match_type.matcher(),
             mapper: UserEventMapper::new(),
             theme,
             ..Default::default()
@@ -70,224 +212,1316 @@ impl Tui {
     where
         B: Backend<Error = std::io::Error>,
     {
+        self.list_height = Tui::calc_list_height(terminal.get_frame().area().height);
         loop {
+            self.update_preview();
             terminal.draw(|f| self.render(f))?;
 
             match event::read()? {
+                Event::Key(key) if matches!(self.stage, Stage::Args(_)) => {
+                    if let Some(ret) = self.handle_args_key(key) {
+                        return Ok(ret);
+                    }
+                }
                 Event::Key(key) => match self.mapper.find_event(key) {
                     Some(UserEvent::Quit) => {
                         return Ok(Ret::Quit);
                     }
+                    Some(UserEvent::Back) => {
+                        if !self.back_to_targets() {
+                            return Ok(Ret::Quit);
+                        }
+                    }
                     Some(UserEvent::Down) => {
                         self.select_next();
                     }
                     Some(UserEvent::Up) => {
                         self.select_prev();
                     }
-                    Some(UserEvent::ToggleAction) => {
-                        self.toggle_action();
+                    Some(UserEvent::NextAction) => match self.stage {
+                        Stage::Targets => self.next_action(),
+                        Stage::Features(_) => self.toggle_current_feature(),
+                        Stage::Tests(_) | Stage::Args(_) | Stage::History(_) => {}
+                    },
+                    Some(UserEvent::PrevAction) => {
+                        if let Stage::Targets = self.stage {
+                            self.prev_action();
+                        }
+                    }
+                    Some(UserEvent::ListTests) => {
+                        if let Some(target) = self.get_current_target_to_list_tests() {
+                            terminal.draw(|f| self.render_loading(f))?;
+                            self.enter_tests(target);
+                        }
+                    }
+                    Some(UserEvent::NextProfile) => {
+                        if !matches!(self.stage, Stage::History(_)) {
+                            self.next_profile();
+                        }
+                    }
+                    Some(UserEvent::NextMatchType) => {
+                        self.next_match_type();
+                    }
+                    Some(UserEvent::EditArgs) => {
+                        if let Stage::Targets = self.stage {
+                            if let Some(target) = self.get_current_target() {
+                                self.enter_args(target);
+                            }
+                        }
+                    }
+                    Some(UserEvent::SelectFeatures) => {
+                        if let Stage::Targets = self.stage {
+                            if let Some(target) = self.get_current_target() {
+                                self.enter_features(target);
+                            }
+                        }
+                    }
+                    Some(UserEvent::GroupByPackage) => {
+                        if let Stage::Targets = self.stage {
+                            self.set_grouped(!self.grouped);
+                        }
+                    }
+                    Some(UserEvent::ToggleGroup) => {
+                        if let Stage::Targets = self.stage {
+                            self.toggle_current_group();
+                        }
+                    }
+                    Some(UserEvent::TogglePreview) => {
+                        if let Stage::Targets = self.stage {
+                            self.show_preview ^= true;
+                        }
+                    }
+                    // a collapsed header is expanded instead of being executed
+                    Some(UserEvent::Execute) if self.current_header().is_some() => {
+                        self.toggle_current_group();
                     }
                     Some(UserEvent::Execute) => {
-                        let ret = match self.get_current_target() {
-                            Some(target) => Ret::Selected(target, self.action),
-                            None => Ret::NotSelected,
-                        };
-                        return Ok(ret);
+                        return Ok(self.selected());
                     }
                     _ => {
                         self.input.handle_event(&Event::Key(key));
                         self.update_filter();
                     }
                 },
-                Event::Resize(_, h) => {
-                    self.list_height = Tui::calc_list_height(h);
+                // the height of the inline viewport does not change with the terminal
+                Event::Resize(_, _) => {
+                    terminal.autoresize()?;
+                    self.list_height = Tui::calc_list_height(terminal.get_frame().area().height);
                 }
                 _ => {}
             }
         }
     }
 
+    // the item under the cursor, as if it was selected with Enter
+    pub fn selected(&self) -> Ret {
+        match &self.stage {
+            Stage::Targets => match self.get_current_target() {
+                Some(target) => {
+                    let action = self.current_action_for(&target);
+                    let options = self.exec_options(&target);
+                    Ret::Selected(target, action, options)
+                }
+                None => Ret::NotSelected,
+            },
+            Stage::Tests(stage) => match self.get_current_test() {
+                Some(name) => {
+                    let options = self.exec_options(&stage.target);
+                    Ret::SelectedTest(stage.target.clone(), name, options)
+                }
+                None => Ret::NotSelected,
+            },
+            Stage::Features(stage) => {
+                let target = stage.target.clone();
+                let action = self.current_action_for(&target);
+                let options = self.exec_options(&target);
+                Ret::Selected(target, action, options)
+            }
+            Stage::History(_) => match self.get_current_history_entry() {
+                Some(entry) => Ret::SelectedHistory(entry.clone()),
+                None => Ret::NotSelected,
+            },
+            Stage::Args(_) => unreachable!(),
+        }
+    }
+
+    pub fn set_query(&mut self, query: &str) {
+        self.input = Input::new(query.into());
+        self.update_filter();
+    }
+
+    pub fn matched_len(&self) -> usize {
+        self.filtered.len()
+    }
+
+    pub fn query_error(&self) -> Option<&str> {
+        self.query_error.as_deref()
+    }
+
+    pub fn set_last_runs(&mut self, last_runs: HashMap<String, u64>) {
+        self.last_runs = last_runs;
+    }
+
+    pub fn set_frecency(&mut self, frecency: HashMap<String, f64>) {
+        self.frecency = frecency;
+        self.update_filter();
+    }
+
+    // keeps the cursor on the current target
+    pub fn set_grouped(&mut self, grouped: bool) {
+        let current = self.current_filtered().map(|ft| ft.index);
+        self.grouped = grouped;
+        self.update_rows();
+        let row = current.and_then(|index| {
+            self.rows.iter().position(|r| match r {
+                Row::Item(i) => self.filtered[*i].index == index,
+                Row::Header { .. } => false,
+            })
+        });
+        self.cursor = row.unwrap_or_else(|| self.first_selectable());
+        self.scroll_to(self.cursor);
+    }
+
+    pub fn set_preview(&mut self, config: &PreviewConfig) {
+        self.show_preview = config.show;
+        self.preview_position = config.position;
+    }
+
+    // loads the source only when the selected target changes
+    fn update_preview(&mut self) {
+        // the list of the other stages does not index the targets
+        if !self.show_preview || !matches!(self.stage, Stage::Targets | Stage::Args(_)) {
+            return;
+        }
+        let Some(target) = self.get_current_target() else {
+            return;
+        };
+        if self
+            .preview
+            .as_ref()
+            .is_some_and(|(p, _)| *p == target.src_path)
+        {
+            return;
+        }
+        let source = preview::load(&target.src_path);
+        self.preview = Some((target.src_path, source));
+    }
+
+    pub fn enter_history(&mut self, entries: Vec<HistoryEntry>) {
+        let labels = entries.iter().map(|e| e.label()).collect();
+        self.stage = Stage::History(Box::new(HistoryStage {
+            entries,
+            labels,
+            now: history::now(),
+        }));
+        self.update_filter();
+    }
+
+    pub fn workspace_state(&self) -> WorkspaceState {
+        WorkspaceState {
+            profile: Some(self.profile.clone()),
+            ..self.state.clone()
+        }
+    }
+
+    fn handle_args_key(&mut self, key: KeyEvent) -> Option<Ret> {
+        match self.mapper.find_event(key) {
+            Some(UserEvent::Quit) => return Some(Ret::Quit),
+            Some(UserEvent::Back) => {
+                self.back_to_targets();
+            }
+            Some(UserEvent::Up) => self.select_args_history(true),
+            Some(UserEvent::Down) => self.select_args_history(false),
+            Some(UserEvent::NextAction) => self.next_action(),
+            Some(UserEvent::PrevAction) => self.prev_action(),
+            Some(UserEvent::NextProfile) => self.next_profile(),
+            Some(UserEvent::Execute) => return self.execute_with_args(),
+            Some(
+                UserEvent::ListTests
+                | UserEvent::SelectFeatures
+                | UserEvent::EditArgs
+                | UserEvent::NextMatchType
+                | UserEvent::GroupByPackage
+                | UserEvent::ToggleGroup
+                | UserEvent::TogglePreview,
+            ) => {}
+            None => {
+                if let Stage::Args(stage) = &mut self.stage {
+                    stage.input.handle_event(&Event::Key(key));
+                    stage.error = None;
+                }
+            }
+        }
+        None
+    }
+
+    fn enter_args(&mut self, target: Target) {
+        let history = self.state.args_history(&target);
+        let (input, history_index) = match history.last() {
+            Some(args) => (Input::new(args.clone()), Some(history.len() - 1)),
+            None => (Input::default(), None),
+        };
+        self.stage = Stage::Args(Box::new(ArgsStage {
+            target,
+            input,
+            history_index,
+            error: None,
+        }));
+    }
+
+    fn select_args_history(&mut self, older: bool) {
+        let Stage::Args(stage) = &mut self.stage else {
+            return;
+        };
+        let history = self.state.args_history(&stage.target);
+        if history.is_empty() {
+            return;
+        }
+        stage.history_index = match (stage.history_index, older) {
+            (None, true) => Some(history.len() - 1),
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) if i + 1 < history.len() => Some(i + 1),
+            (_, false) => None,
+        };
+        let args = stage
+            .history_index
+            .map(|i| history[i].clone())
+            .unwrap_or_default();
+        stage.input = Input::new(args);
+        stage.error = None;
+    }
+
+    fn execute_with_args(&mut self) -> Option<Ret> {
+        let Stage::Args(stage) = &mut self.stage else {
+            return None;
+        };
+        let value = stage.input.value().to_string();
+        let args = match split_words(&value) {
+            Ok(args) => args,
+            Err(e) => {
+                stage.error = Some(e);
+                return None;
+            }
+        };
+        let target = stage.target.clone();
+        if !args.is_empty() {
+            self.state.push_args_history(&target, &value);
+        }
+        let action = self.current_action_for(&target);
+        let options = ExecOptions {
+            args,
+            ..self.exec_options(&target)
+        };
+        Some(Ret::Selected(target, action, options))
+    }
+
     fn calc_list_height(h: u16) -> usize {
-        (h - 1) as usize
+        h.saturating_sub(1) as usize
     }
 
     fn select_next(&mut self) {
-        if self.cursor < self.filtered.len() - 1 {
-            if self.cursor - self.list_offset == self.list_height - 1 {
-                self.list_offset += 1;
-            }
-            self.cursor += 1;
+        let next = (self.cursor + 1..self.rows.len()).find(|i| self.rows[*i].is_selectable());
+        if let Some(i) = next {
+            self.cursor = i;
+            self.scroll_to(i);
         }
     }
 
     fn select_prev(&mut self) {
-        if self.cursor > 0 {
-            if self.cursor - self.list_offset == 0 {
-                self.list_offset -= 1;
-            }
-            self.cursor -= 1;
+        let prev = (0..self.cursor)
+            .rev()
+            .find(|i| self.rows[*i].is_selectable());
+        if let Some(i) = prev {
+            self.cursor = i;
+            // the header of the first target of a group is shown together
+            let top = if i > 0 && !self.rows[i - 1].is_selectable() {
+                i - 1
+            } else {
+                i
+            };
+            self.scroll_to(top);
+        }
+    }
+
+    // scrolls the list so that the row `i` is visible
+    fn scroll_to(&mut self, i: usize) {
+        if i < self.list_offset {
+            self.list_offset = i;
+        } else if i >= self.list_offset + self.list_height {
+            self.list_offset = i + 1 - self.list_height;
         }
     }
 
-    fn toggle_action(&mut self) {
-        self.action = match self.action {
-            Action::Run => Action::Build,
-            Action::Build => Action::Run,
+    fn first_selectable(&self) -> usize {
+        self.rows
+            .iter()
+            .position(Row::is_selectable)
+            .unwrap_or_default()
+    }
+
+    // collapses the group of the current target, or expands the current collapsed header
+    fn toggle_current_group(&mut self) {
+        if !self.grouped {
+            return;
+        }
+        let package = match self.rows.get(self.cursor) {
+            Some(Row::Header { package, .. }) => package.clone(),
+            Some(Row::Item(i)) => self.targets[self.filtered[*i].index].package.clone(),
+            None => return,
+        };
+        if !self.collapsed.remove(&package) {
+            self.collapsed.insert(package.clone());
+        }
+        self.update_rows();
+        let header = self
+            .rows
+            .iter()
+            .position(|r| matches!(r, Row::Header { package: p, .. } if *p == package))
+            .unwrap_or_default();
+        self.scroll_to(header);
+        // the first target of the expanded group
+        self.cursor = (header..self.rows.len())
+            .find(|i| self.rows[*i].is_selectable())
+            .unwrap_or(header);
+    }
+
+    fn next_action(&mut self) {
+        let target = self.get_current_target();
+        let kind = target.as_ref().map(|t| t.kind);
+        self.action = self
+            .current_action(target.as_ref())
+            .next(&self.actions, kind);
+    }
+
+    fn prev_action(&mut self) {
+        let target = self.get_current_target();
+        let kind = target.as_ref().map(|t| t.kind);
+        self.action = self
+            .current_action(target.as_ref())
+            .prev(&self.actions, kind);
+    }
+
+    fn next_profile(&mut self) {
+        let pos = self.profiles.iter().position(|p| *p == self.profile);
+        let next = pos.map(|i| (i + 1) % self.profiles.len()).unwrap_or(0);
+        if let Some(profile) = self.profiles.get(next) {
+            self.profile = profile.clone();
+        }
+    }
+
+    fn next_match_type(&mut self) {
+        self.match_type = self.match_type.next();
+        self.matcher = self.match_type.matcher();
+        self.update_filter();
+    }
+
+    // falls back to `Run`, or `Test` for a lib, if the chosen action does not apply to the target
+    fn current_action(&self, target: Option<&Target>) -> Action {
+        match target {
+            Some(t) if !self.action.is_applicable(t.kind) => match t.kind {
+                TargetKind::Lib => Action::Test,
+                _ => Action::Run,
+            },
+            _ => self.action.clone(),
+        }
+    }
+
+    fn current_action_for(&self, target: &Target) -> Action {
+        self.current_action(Some(target))
+    }
+
+    fn exec_options(&self, target: &Target) -> ExecOptions {
+        ExecOptions {
+            features: self.feature_selection(target),
+            profile: self.profile.clone(),
+            args: Vec::new(),
+        }
+    }
+
+    fn feature_selection(&self, target: &Target) -> FeatureSelection {
+        self.feature_selections
+            .get(&target.package)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn enter_features(&mut self, target: Target) {
+        let items = [NO_DEFAULT_FEATURES, ALL_FEATURES]
+            .into_iter()
+            .map(String::from)
+            .chain(target.package_features.keys().cloned())
+            .collect();
+        let prev_input = std::mem::take(&mut self.input);
+        self.stage = Stage::Features(Box::new(FeaturesStage {
+            target,
+            items,
+            prev_input,
+        }));
+        self.update_filter();
+    }
+
+    fn toggle_current_feature(&mut self) {
+        let Stage::Features(stage) = &self.stage else {
+            return;
+        };
+        let Some(item) = self
+            .current_filtered()
+            .and_then(|ft| stage.items.get(ft.index))
+        else {
+            return;
         };
+        let selection = self
+            .feature_selections
+            .entry(stage.target.package.clone())
+            .or_default();
+        match item.as_str() {
+            NO_DEFAULT_FEATURES => selection.no_default_features ^= true,
+            ALL_FEATURES => selection.all_features ^= true,
+            feature => selection.toggle_feature(feature),
+        }
+    }
+
+    fn current_filtered(&self) -> Option<&FilteredTarget> {
+        match self.rows.get(self.cursor)? {
+            Row::Item(i) => self.filtered.get(*i),
+            Row::Header { .. } => None,
+        }
+    }
+
+    fn current_header(&self) -> Option<&str> {
+        match self.rows.get(self.cursor)? {
+            Row::Header { package, .. } => Some(package),
+            Row::Item(_) => None,
+        }
     }
 
     fn get_current_target(&self) -> Option<Target> {
-        self.filtered
-            .get(self.cursor)
+        self.current_filtered()
             .and_then(|t| self.targets.get(t.index))
             .cloned()
     }
 
+    fn get_current_target_to_list_tests(&self) -> Option<Target> {
+        match self.stage {
+            Stage::Targets => self.get_current_target().filter(cargo::can_list_tests),
+            Stage::Tests(_) | Stage::Features(_) | Stage::Args(_) | Stage::History(_) => None,
+        }
+    }
+
+    fn get_current_history_entry(&self) -> Option<&HistoryEntry> {
+        match &self.stage {
+            Stage::History(stage) => self
+                .current_filtered()
+                .and_then(|t| stage.entries.get(t.index)),
+            _ => None,
+        }
+    }
+
+    fn get_current_test(&self) -> Option<String> {
+        self.current_filtered()
+            .and_then(|t| self.test_names().get(t.index))
+            .cloned()
+    }
+
+    fn test_names(&self) -> &[String] {
+        match &self.stage {
+            Stage::Tests(stage) => stage.tests.as_deref().unwrap_or_default(),
+            Stage::Targets | Stage::Features(_) | Stage::Args(_) | Stage::History(_) => &[],
+        }
+    }
+
+    fn enter_tests(&mut self, target: Target) {
+        let tests = cargo::list_tests(&target, &self.exec_options(&target));
+        let prev_input = std::mem::take(&mut self.input);
+        self.stage = Stage::Tests(Box::new(TestsStage {
+            target,
+            tests,
+            prev_input,
+        }));
+        self.update_filter();
+    }
+
+    fn back_to_targets(&mut self) -> bool {
+        match std::mem::take(&mut self.stage) {
+            Stage::Targets => false,
+            // the history list is opened instead of the targets
+            Stage::History(stage) => {
+                self.stage = Stage::History(stage);
+                false
+            }
+            Stage::Tests(stage) => {
+                self.input = stage.prev_input;
+                self.update_filter();
+                true
+            }
+            Stage::Features(stage) => {
+                self.input = stage.prev_input;
+                self.update_filter();
+                true
+            }
+            // the filter is kept as is while editing args
+            Stage::Args(_) => true,
+        }
+    }
+
     fn update_filter(&mut self) {
-        let s = self.input.value();
-        self.filtered = self
-            .targets
-            .iter()
+        // the previous result is kept while the query is invalid
+        let query = match self.matcher.parse_query(self.input.value(), self.case) {
+            Ok(query) => query,
+            Err(e) => {
+                self.query_error = Some(e);
+                return;
+            }
+        };
+        self.query_error = None;
+        let names: Vec<&str> = match self.stage {
+            Stage::Targets | Stage::Args(_) => {
+                self.filtered = self
+                    .targets
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, t)| {
+                        let m = self
+                            .matcher
+                            .match_target(&t.name, &t.package, &t.path, &query)?;
+                        let frecency = self.frecency.get(&target_key(t)).copied();
+                        let bonus = frecency.map(history::frecency_bonus).unwrap_or_default();
+                        Some(FilteredTarget {
+                            index: i,
+                            score: m.score + bonus,
+                            match_indices: m.name,
+                            package_match_indices: m.package,
+                            path_match_indices: m.path,
+                        })
+                    })
+                    .collect();
+                self.sort_filtered();
+                return;
+            }
+            Stage::Tests(_) => self.test_names().iter().map(|n| n.as_str()).collect(),
+            Stage::Features(ref stage) => stage.items.iter().map(|n| n.as_str()).collect(),
+            Stage::History(ref stage) => stage.labels.iter().map(|n| n.as_str()).collect(),
+        };
+        self.filtered = names
+            .into_iter()
             .enumerate()
-            .filter_map(|(i, t)| {
+            .filter_map(|(i, name)| {
                 self.matcher
-                    .match_indices(&t.name, s)
-                    .map(|indices| FilteredTarget {
+                    .match_query(name, &query)
+                    .map(|(score, indices)| FilteredTarget {
                         index: i,
+                        score,
                         match_indices: indices,
+                        ..Default::default()
                     })
             })
             .collect();
-        self.cursor = 0;
+        self.sort_filtered();
+    }
+
+    // stable, so equally scored items keep their original (frecency) order.
+    // the history is kept newest first.
+    fn sort_filtered(&mut self) {
+        if !matches!(self.stage, Stage::History(_)) {
+            self.filtered.sort_by_key(|f| Reverse(f.score));
+        }
+        self.update_rows();
+        self.cursor = self.first_selectable();
         self.list_offset = 0;
     }
 
+    // the groups are ordered by their best ranked targets, which keep their order in the group
+    fn update_rows(&mut self) {
+        let grouped = self.grouped && matches!(self.stage, Stage::Targets | Stage::Args(_));
+        if !grouped {
+            self.rows = (0..self.filtered.len()).map(Row::Item).collect();
+            return;
+        }
+        let mut groups: Vec<(&str, Vec<usize>)> = Vec::new();
+        for (i, ft) in self.filtered.iter().enumerate() {
+            let package = self.targets[ft.index].package.as_str();
+            match groups.iter_mut().find(|(p, _)| *p == package) {
+                Some((_, items)) => items.push(i),
+                None => groups.push((package, vec![i])),
+            }
+        }
+        let mut rows = Vec::new();
+        for (package, items) in groups {
+            let collapsed = self.collapsed.contains(package);
+            rows.push(Row::Header {
+                package: package.into(),
+                len: items.len(),
+                collapsed,
+            });
+            if !collapsed {
+                rows.extend(items.into_iter().map(Row::Item));
+            }
+        }
+        self.rows = rows;
+    }
+
+    // the position of the cursor and the number of the selectable rows
+    fn selectable_position(&self) -> (usize, usize) {
+        let selectable = |r: &&Row| r.is_selectable();
+        let pos = self
+            .rows
+            .iter()
+            .take(self.cursor + 1)
+            .filter(selectable)
+            .count();
+        let total = self.rows.iter().filter(selectable).count();
+        (pos, total)
+    }
+
+    fn total_len(&self) -> usize {
+        match self.stage {
+            Stage::Targets | Stage::Args(_) => self.targets.len(),
+            Stage::Tests(_) => self.test_names().len(),
+            Stage::Features(ref stage) => stage.items.len(),
+            Stage::History(ref stage) => stage.entries.len(),
+        }
+    }
+
+    fn show_features(&self) -> bool {
+        self.show_features || self.feature_selections.values().any(|s| !s.is_empty())
+    }
+
     fn render(&self, f: &mut Frame) {
         let block = Block::default().bg(self.theme.bg);
         f.render_widget(block, f.area());
 
+        if is_too_small(f.area()) {
+            self.render_message(f, f.area(), "terminal too small");
+            return;
+        }
+
         let chunks = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).split(f.area());
         self.render_input(f, chunks[0]);
-        self.render_list(f, chunks[1]);
+        match &self.stage {
+            Stage::Targets => self.render_list_with_preview(f, chunks[1]),
+            Stage::Tests(stage) => match &stage.tests {
+                Ok(_) => self.render_test_list(f, chunks[1]),
+                Err(e) => self.render_message(f, chunks[1], e),
+            },
+            Stage::Features(stage) => self.render_feature_list(f, chunks[1], stage),
+            Stage::History(stage) => self.render_history_list(f, chunks[1], stage),
+            Stage::Args(stage) => {
+                let chunks =
+                    Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).split(chunks[1]);
+                self.render_args_input(f, chunks[0], stage);
+                self.render_list_with_preview(f, chunks[1]);
+            }
+        }
     }
 
-    fn render_input(&self, f: &mut Frame, area: Rect) {
-        let targets_num_digits = digits(self.targets.len());
+    fn render_args_input(&self, f: &mut Frame, area: Rect, stage: &ArgsStage) {
         let max_w = area.width as usize;
         let label_w = 7;
-        let num_w = targets_num_digits * 2 + 5;
-        let input_w = max_w - (label_w + num_w + 3);
-
-        let (label, label_bg, label_fg) = match self.action {
-            Action::Run => (
-                "  run  ",
-                self.theme.action_run_bg,
-                self.theme.action_run_fg,
-            ),
-            Action::Build => (
-                " build ",
-                self.theme.action_build_bg,
-                self.theme.action_build_fg,
-            ),
-        };
-        let input = format!("{:input_w$}", self.input.value());
-        let nums = if self.filtered.is_empty() {
-            "".to_string()
-        } else {
-            format!(
-                "({:targets_num_digits$} / {:targets_num_digits$})",
-                self.cursor + 1,
-                self.filtered.len()
-            )
-        };
+        let error = stage
+            .error
+            .as_ref()
+            .map(|e| format!("({e})"))
+            .unwrap_or_default();
+        let error_w = console::measure_text_width(&error);
+        let input_w = max_w.saturating_sub(label_w + error_w + 3);
+
+        let input = pad_text(stage.input.value(), input_w, Align::Left);
         let spans = vec![
-            label.bg(label_bg).fg(label_fg),
+            "  args "
+                .bg(self.theme.args_label_bg)
+                .fg(self.theme.args_label_fg),
             " ".into(),
             input.fg(self.theme.input_fg),
             " ".into(),
-            nums.fg(self.theme.numbers_fg),
+            error.fg(self.theme.error_fg),
             " ".into(),
         ];
+        f.render_widget(Paragraph::new(Line::from(spans)), area);
+
+        let x = label_w + 1 + stage.input.visual_cursor();
+        f.set_cursor_position(cursor_position(area, x));
+    }
+
+    fn render_loading(&self, f: &mut Frame) {
+        let block = Block::default().bg(self.theme.bg);
+        f.render_widget(block, f.area());
+
+        let chunks = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).split(f.area());
+        self.render_input(f, chunks[0]);
+        self.render_message(f, chunks[1], "listing tests...");
+    }
+
+    fn render_message(&self, f: &mut Frame, area: Rect, msg: &str) {
+        let line = Line::from(vec![" ".into(), msg.fg(self.theme.path_fg)]);
+        f.render_widget(Paragraph::new(line), area);
+    }
+
+    fn render_input(&self, f: &mut Frame, area: Rect) {
+        let targets_num_digits = digits(self.total_len());
+        let max_w = area.width as usize;
+        let label_w = 7;
+        let profile = match self.get_current_history_entry() {
+            Some(entry) => format!(" {} ", entry.profile),
+            None => format!(" {} ", self.profile),
+        };
+        let profile_w = console::measure_text_width(&profile);
+        let (nums, nums_fg) = if let Some(e) = &self.query_error {
+            (format!("({e})"), self.theme.error_fg)
+        } else {
+            let (pos, total) = self.selectable_position();
+            let nums = if total == 0 {
+                "".to_string()
+            } else {
+                format!("({pos:targets_num_digits$} / {total:targets_num_digits$})")
+            };
+            (nums, self.theme.numbers_fg)
+        };
+        let num_w = (targets_num_digits * 2 + 5).max(console::measure_text_width(&nums));
+        let match_type = format!(" {} ", self.match_type.name());
+        let match_type_w = console::measure_text_width(&match_type);
+        // the badges are dropped in this order while the input is too narrow
+        let mut parts = [(true, match_type_w), (true, num_w), (true, profile_w)];
+        let parts_w = |parts: &[(bool, usize)]| -> usize {
+            parts.iter().filter(|(s, _)| *s).map(|(_, w)| w + 1).sum()
+        };
+        for i in 0..parts.len() {
+            if max_w.saturating_sub(label_w + 2 + parts_w(&parts)) >= MIN_INPUT_W {
+                break;
+            }
+            parts[i].0 = false;
+        }
+        let input_w = max_w.saturating_sub(label_w + 2 + parts_w(&parts));
+        let [(show_match_type, _), (show_nums, _), (show_profile, _)] = parts;
+
+        let action = match &self.stage {
+            Stage::Targets => self.current_action(self.get_current_target().as_ref()),
+            Stage::Tests(_) => Action::Test,
+            Stage::Features(stage) => self.current_action_for(&stage.target),
+            Stage::Args(stage) => self.current_action_for(&stage.target),
+            Stage::History(_) => self
+                .get_current_history_entry()
+                .and_then(|e| Action::find(&self.actions, &e.action))
+                .unwrap_or_default(),
+        };
+        let (label, label_bg, label_fg) = self.action_label(&action);
+        let input = pad_text(self.input.value(), input_w, Align::Left);
+        let mut spans = vec![label.bg(label_bg).fg(label_fg), " ".into()];
+        if show_profile {
+            spans.push(profile.bg(self.theme.profile_bg).fg(self.theme.profile_fg));
+            spans.push(" ".into());
+        }
+        spans.push(input.fg(self.theme.input_fg));
+        spans.push(" ".into());
+        if show_match_type {
+            spans.push(
+                match_type
+                    .bg(self.theme.match_type_bg)
+                    .fg(self.theme.match_type_fg),
+            );
+            spans.push(" ".into());
+        }
+        if show_nums {
+            spans.push(nums.fg(nums_fg));
+            spans.push(" ".into());
+        }
         let line = Paragraph::new(Line::from(spans));
         f.render_widget(line, area);
 
-        let x = area.x + 8 + (self.input.visual_cursor() as u16);
-        let y = area.y;
-        f.set_cursor_position((x, y));
+        if !matches!(self.stage, Stage::Args(_)) {
+            let input_x = label_w + 1 + if show_profile { profile_w + 1 } else { 0 };
+            let x = input_x + self.input.visual_cursor();
+            f.set_cursor_position(cursor_position(area, x));
+        }
+    }
+
+    fn action_label(&self, action: &Action) -> (Cow<'static, str>, Color, Color) {
+        let theme = &self.theme;
+        let (label, bg, fg) = match action {
+            Action::Run => ("  run  ", theme.action_run_bg, theme.action_run_fg),
+            Action::Build => (" build ", theme.action_build_bg, theme.action_build_fg),
+            Action::Check => (" check ", theme.action_check_bg, theme.action_check_fg),
+            Action::Clippy => (" clippy", theme.action_clippy_bg, theme.action_clippy_fg),
+            Action::Doc => ("  doc  ", theme.action_doc_bg, theme.action_doc_fg),
+            Action::Test => ("  test ", theme.action_test_bg, theme.action_test_fg),
+            Action::Bench => (" bench ", theme.action_bench_bg, theme.action_bench_fg),
+            Action::Install => ("install", theme.action_install_bg, theme.action_install_fg),
+            Action::Asm => ("  asm  ", theme.action_asm_bg, theme.action_asm_fg),
+            Action::Custom(c) => {
+                let name = truncate_str(&c.name, 7, ELLIPSIS);
+                return (pad_text(&name, 7, Align::Center).into(), c.bg, c.fg);
+            }
+        };
+        (label.into(), bg, fg)
+    }
+
+    // the offset and the height of the visible part of the list in `area`.
+    // the cursor is kept visible even if the area is shorter than usual.
+    fn visible_range(&self, area: Rect) -> (usize, usize) {
+        let list_height = self.list_height.min(area.height as usize);
+        let list_offset = if self.cursor >= self.list_offset + list_height {
+            (self.cursor + 1).saturating_sub(list_height)
+        } else {
+            self.list_offset
+        };
+        (list_offset, list_height)
     }
 
     fn render_list(&self, f: &mut Frame, area: Rect) {
         let max_w = area.width as usize;
+        let show_features = self.show_features();
+        // the package is shown in the headers of the grouped list
+        let show_package = self.show_package && !self.grouped;
+        let specs: Vec<ColumnSpec> = self
+            .columns
+            .iter()
+            .filter(|s| match s.column {
+                Column::Features => show_features,
+                Column::Package => show_package,
+                _ => true,
+            })
+            .copied()
+            .collect();
+        let columns = layout::list_columns(max_w, &specs);
+        let now = history::now();
+        let (list_offset, list_height) = self.visible_range(area);
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .enumerate()
+            .skip(list_offset)
+            .take(list_height)
+            .flat_map(|(i, row)| {
+                let selected = i == self.cursor;
+                match row {
+                    Row::Item(fi) => {
+                        let ft = self.filtered.get(*fi)?;
+                        let t = self.targets.get(ft.index)?;
+                        Some(self.build_list_item(t, selected, &columns, now, ft))
+                    }
+                    Row::Header {
+                        package,
+                        len,
+                        collapsed,
+                    } => Some(self.build_header_item(package, *len, *collapsed, selected, max_w)),
+                }
+            })
+            .collect();
+        let list = List::new(items);
+        f.render_widget(list, area);
+    }
+
+    fn render_list_with_preview(&self, f: &mut Frame, area: Rect) {
+        match self.split_preview(area) {
+            Some((list_area, preview_area)) => {
+                self.render_list(f, list_area);
+                self.render_preview(f, preview_area);
+            }
+            None => self.render_list(f, area),
+        }
+    }
+
+    // the areas of the list and the preview, or `None` if the preview is hidden or does not fit
+    fn split_preview(&self, area: Rect) -> Option<(Rect, Rect)> {
+        if !self.show_preview {
+            return None;
+        }
+        let halves = [Constraint::Percentage(50), Constraint::Percentage(50)];
+        let (list, preview, fits) = match self.preview_position {
+            PreviewPosition::Right => {
+                let chunks = Layout::horizontal(halves).split(area);
+                let fits = chunks[0].width as usize >= layout::MIN_WIDTH
+                    && chunks[1].width as usize >= MIN_PREVIEW_W;
+                (chunks[0], chunks[1], fits)
+            }
+            PreviewPosition::Bottom => {
+                let chunks = Layout::vertical(halves).split(area);
+                let fits = chunks[0].height > 0 && chunks[1].height as usize >= MIN_PREVIEW_H;
+                (chunks[0], chunks[1], fits)
+            }
+        };
+        fits.then_some((list, preview))
+    }
+
+    fn render_preview(&self, f: &mut Frame, area: Rect) {
+        let (borders, padding) = match self.preview_position {
+            PreviewPosition::Right => (Borders::LEFT, Padding::left(1)),
+            PreviewPosition::Bottom => (Borders::TOP, Padding::horizontal(1)),
+        };
+        let block = Block::default()
+            .borders(borders)
+            .border_style(Style::default().fg(self.theme.path_fg))
+            .padding(padding);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        // nothing is shown for a header of the grouped list
+        let Some(target) = self.get_current_target() else {
+            return;
+        };
+        let mut lines: Vec<Line> = Vec::new();
+        if let Some(description) = target.package_description {
+            lines.push(Line::from(description.fg(self.theme.description_fg)));
+        }
+        if !target.required_features.is_empty() {
+            let features = format!("features: {}", target.required_features.join(", "));
+            lines.push(Line::from(features.fg(self.theme.features_fg)));
+        }
+        if !lines.is_empty() {
+            lines.push(Line::default());
+        }
+        match &self.preview {
+            Some((path, Ok(source))) if *path == target.src_path => {
+                let num_w = digits(source.lines.len());
+                let height = (inner.height as usize).saturating_sub(lines.len());
+                for (i, line) in source
+                    .lines
+                    .iter()
+                    .enumerate()
+                    .skip(source.start)
+                    .take(height)
+                {
+                    lines.push(Line::from(vec![
+                        format!("{:>num_w$} ", i + 1).fg(self.theme.numbers_fg),
+                        line.as_str().fg(self.theme.input_fg),
+                    ]));
+                }
+            }
+            Some((path, Err(e))) if *path == target.src_path => {
+                lines.push(Line::from(e.as_str().fg(self.theme.error_fg)));
+            }
+            // not loaded yet
+            _ => {}
+        }
+        f.render_widget(Paragraph::new(lines), inner);
+    }
+
+    fn render_test_list(&self, f: &mut Frame, area: Rect) {
+        let max_w = area.width as usize;
+        let (list_offset, list_height) = self.visible_range(area);
         let items: Vec<ListItem> = self
             .filtered
             .iter()
             .enumerate()
-            .skip(self.list_offset)
-            .take(self.list_height)
+            .skip(list_offset)
+            .take(list_height)
             .flat_map(|(i, ft)| {
                 let selected = i == self.cursor;
-                self.targets
+                self.test_names()
                     .get(ft.index)
-                    .map(|t| self.build_list_item(t, selected, max_w, &ft.match_indices))
+                    .map(|n| self.build_test_list_item(n, selected, max_w, &ft.match_indices))
             })
             .collect();
         let list = List::new(items);
         f.render_widget(list, area);
     }
 
-    fn build_list_item(
+    fn build_test_list_item(
         &self,
-        target: &Target,
+        name: &str,
         selected: bool,
         max_w: usize,
         matched_indices: &[usize],
     ) -> ListItem<'_> {
-        let kind_w: usize = 7;
-        let name_w: usize = 25;
-        let (path_w, features_w) = if self.show_features {
-            let path_w: usize = 30;
-            let features_w: usize = max_w - (kind_w + name_w + path_w + 5);
-            (path_w, features_w)
+        let name_w = max_w.saturating_sub(2);
+        let (name, indices, truncated) =
+            truncate_text(name, name_w, Truncate::Right, matched_indices);
+
+        let mut spans = Vec::new();
+        spans.push(" ".into());
+        spans.extend(self.highlight_name(&name, &indices, truncated));
+
+        let line = Text::from(Line::from(spans));
+        let style = if selected {
+            Style::default().bg(self.theme.selected_bg)
         } else {
-            let path_w: usize = max_w - (kind_w + name_w + 4);
-            (path_w, 0)
+            Style::default()
         };
+        ListItem::new(line).style(style)
+    }
 
-        let kind = match target.kind {
-            TargetKind::Bin => "bin",
-            TargetKind::Example => "example",
+    fn render_history_list(&self, f: &mut Frame, area: Rect, stage: &HistoryStage) {
+        let max_w = area.width as usize;
+        let (list_offset, list_height) = self.visible_range(area);
+        let items: Vec<ListItem> = self
+            .filtered
+            .iter()
+            .enumerate()
+            .skip(list_offset)
+            .take(list_height)
+            .flat_map(|(i, ft)| {
+                let selected = i == self.cursor;
+                let entry = stage.entries.get(ft.index)?;
+                let label = stage.labels.get(ft.index)?;
+                let item = self.build_history_list_item(
+                    entry,
+                    label,
+                    stage.now,
+                    selected,
+                    max_w,
+                    &ft.match_indices,
+                );
+                Some(item)
+            })
+            .collect();
+        let list = List::new(items);
+        f.render_widget(list, area);
+    }
+
+    fn build_history_list_item(
+        &self,
+        entry: &HistoryEntry,
+        label: &str,
+        now: u64,
+        selected: bool,
+        max_w: usize,
+        matched_indices: &[usize],
+    ) -> ListItem<'_> {
+        let age_w: usize = 4;
+        let action_w: usize = 7;
+        let status = match entry.status {
+            Some(0) => "".to_string(),
+            Some(code) => format!("exit {code}"),
+            None => "signal".to_string(),
         };
-        let name = truncate_str(&target.name, name_w, ELLIPSIS);
-        let path = truncate_str(&target.path, path_w, ELLIPSIS);
-        let features = if target.required_features.is_empty() {
-            "".to_string()
+        let status_w = console::measure_text_width(&status);
+        let label_w = max_w.saturating_sub(age_w + action_w + status_w + 5);
+
+        let age = history::format_age(entry.timestamp, now);
+        let action = truncate_str(&entry.action, action_w, ELLIPSIS);
+        let (label, indices, truncated) =
+            truncate_text(label, label_w, Truncate::Right, matched_indices);
+
+        let mut label_spans = self.highlight_name(&label, &indices, truncated);
+        let label_len = console::measure_text_width(&label);
+        if label_len < label_w {
+            label_spans.push(" ".repeat(label_w - label_len).into());
+        }
+
+        let mut spans = Vec::new();
+        spans.push(" ".into());
+        spans.push(format!("{age:>age_w$}").fg(self.theme.path_fg));
+        spans.push(" ".into());
+        spans.push(format!("{action:action_w$}").fg(self.theme.kind_fg));
+        spans.push(" ".into());
+        spans.extend(label_spans);
+        spans.push(" ".into());
+        spans.push(status.fg(self.theme.error_fg));
+
+        let line = Text::from(Line::from(spans));
+        let style = if selected {
+            Style::default().bg(self.theme.selected_bg)
         } else {
-            let s = format!("--features {:?}", target.required_features);
-            truncate_str(&s, features_w, ELLIPSIS).into()
+            Style::default()
         };
+        ListItem::new(line).style(style)
+    }
 
-        let mut name_mt = highlight_matched_text(vec![name.to_string().into()])
-            .matched_indices(matched_indices.to_vec())
-            .not_matched_style(Style::default().fg(self.theme.name_fg))
-            .matched_style(Style::default().fg(self.theme.name_match_fg));
-        if name.ends_with(ELLIPSIS) {
-            name_mt = name_mt.ellipsis(ELLIPSIS);
-        }
-        let mut name_spans = name_mt.into_spans();
-        if name.len() < name_w {
-            name_spans.push(" ".repeat(name_w - name.len()).into());
+    fn render_feature_list(&self, f: &mut Frame, area: Rect, stage: &FeaturesStage) {
+        let max_w = area.width as usize;
+        let (list_offset, list_height) = self.visible_range(area);
+        let selection = self.feature_selection(&stage.target);
+        let name_w = stage
+            .items
+            .iter()
+            .map(|n| console::measure_text_width(n))
+            .max()
+            .unwrap_or_default()
+            .min(30);
+        let items: Vec<ListItem> = self
+            .filtered
+            .iter()
+            .enumerate()
+            .skip(list_offset)
+            .take(list_height)
+            .flat_map(|(i, ft)| {
+                let selected = i == self.cursor;
+                stage.items.get(ft.index).map(|name| {
+                    let (checked, deps) = match name.as_str() {
+                        NO_DEFAULT_FEATURES => (selection.no_default_features, None),
+                        ALL_FEATURES => (selection.all_features, None),
+                        feature => (
+                            selection.features.iter().any(|f| f == feature),
+                            stage.target.package_features.get(feature),
+                        ),
+                    };
+                    self.build_feature_list_item(
+                        name,
+                        checked,
+                        deps,
+                        selected,
+                        name_w,
+                        max_w,
+                        &ft.match_indices,
+                    )
+                })
+            })
+            .collect();
+        let list = List::new(items);
+        f.render_widget(list, area);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_feature_list_item(
+        &self,
+        name: &str,
+        checked: bool,
+        deps: Option<&Vec<String>>,
+        selected: bool,
+        name_w: usize,
+        max_w: usize,
+        matched_indices: &[usize],
+    ) -> ListItem<'_> {
+        let check_w: usize = 3;
+        let deps_w = max_w.saturating_sub(check_w + name_w + 4);
+
+        let check = if checked { "[x]" } else { "[ ]" };
+        let (name, indices, truncated) =
+            truncate_text(name, name_w, Truncate::Right, matched_indices);
+        let deps = deps
+            .map(|deps| format!("= [{}]", deps.join(", ")))
+            .unwrap_or_default();
+        let deps = truncate_str(&deps, deps_w, ELLIPSIS);
+
+        let mut name_spans = self.highlight_name(&name, &indices, truncated);
+        let name_len = console::measure_text_width(&name);
+        if name_len < name_w {
+            name_spans.push(" ".repeat(name_w - name_len).into());
         }
 
         let mut spans = Vec::new();
         spans.push(" ".into());
-        spans.push(format!("{kind:kind_w$}").fg(self.theme.kind_fg));
+        spans.push(check.fg(self.theme.kind_fg));
         spans.push(" ".into());
         spans.extend(name_spans);
         spans.push(" ".into());
-        spans.push(format!("{path:path_w$}").fg(self.theme.path_fg));
+        spans.push(deps.to_string().fg(self.theme.features_fg));
+
+        let line = Text::from(Line::from(spans));
+        let style = if selected {
+            Style::default().bg(self.theme.selected_bg)
+        } else {
+            Style::default()
+        };
+        ListItem::new(line).style(style)
+    }
+
+    fn features_text(&self, target: &Target) -> String {
+        let selection = self.feature_selection(target);
+        let features = cargo::all_features(target, &selection);
+        let mut texts = Vec::new();
+        if !features.is_empty() {
+            texts.push(format!("--features {features:?}"));
+        }
+        if selection.no_default_features {
+            texts.push(NO_DEFAULT_FEATURES.to_string());
+        }
+        if selection.all_features {
+            texts.push(ALL_FEATURES.to_string());
+        }
+        texts.join(" ")
+    }
+
+    fn highlight_name<'a>(
+        &self,
+        name: &str,
+        matched_indices: &[usize],
+        truncated: bool,
+    ) -> Vec<Span<'a>> {
+        self.highlight_text(name, matched_indices, truncated, self.theme.name_fg)
+    }
+
+    fn highlight_text<'a>(
+        &self,
+        text: &str,
+        matched_indices: &[usize],
+        truncated: bool,
+        fg: Color,
+    ) -> Vec<Span<'a>> {
+        let style = Style::default().fg(fg);
+        let matched_style = Style::default().fg(self.theme.name_match_fg);
+        highlight_spans(text, matched_indices, truncated)
+            .into_iter()
+            .map(|(s, matched)| Span::styled(s, if matched { matched_style } else { style }))
+            .collect()
+    }
+
+    fn build_header_item(
+        &self,
+        package: &str,
+        len: usize,
+        collapsed: bool,
+        selected: bool,
+        max_w: usize,
+    ) -> ListItem<'_> {
+        let marker = if collapsed { "+" } else { "-" };
+        let header = format!("{marker} {package} ({len})");
+        let header = truncate_str(&header, max_w.saturating_sub(2), ELLIPSIS);
+
+        let spans = vec![
+            " ".into(),
+            header.to_string().fg(self.theme.package_fg).bold(),
+        ];
+
+        let line = Text::from(Line::from(spans));
+        let style = if selected {
+            Style::default().bg(self.theme.selected_bg)
+        } else {
+            Style::default()
+        };
+        ListItem::new(line).style(style)
+    }
+
+    fn build_list_item(
+        &self,
+        target: &Target,
+        selected: bool,
+        columns: &[(ColumnSpec, usize)],
+        now: u64,
+        filtered: &FilteredTarget,
+    ) -> ListItem<'_> {
+        let mut spans = Vec::new();
         spans.push(" ".into());
-        if self.show_features {
-            spans.push(format!("{features:features_w$}").fg(self.theme.features_fg));
+        for (spec, w) in columns {
+            let (text, indices, fg): (Cow<str>, &[usize], Color) = match spec.column {
+                Column::Kind => (target.kind.name().into(), &[], self.theme.kind_fg),
+                Column::Name => (
+                    target.name.as_str().into(),
+                    &filtered.match_indices,
+                    self.theme.name_fg,
+                ),
+                Column::Package => (
+                    target.package.as_str().into(),
+                    &filtered.package_match_indices,
+                    self.theme.package_fg,
+                ),
+                Column::Path => (
+                    target.path.as_str().into(),
+                    &filtered.path_match_indices,
+                    self.theme.path_fg,
+                ),
+                Column::Features => (
+                    self.features_text(target).into(),
+                    &[],
+                    self.theme.features_fg,
+                ),
+                Column::Description => (
+                    target.package_description.as_deref().unwrap_or("").into(),
+                    &[],
+                    self.theme.description_fg,
+                ),
+                Column::LastRun => {
+                    let age = self
+                        .last_runs
+                        .get(&target_key(target))
+                        .map(|ts| history::format_age(*ts, now))
+                        .unwrap_or_default();
+                    (age.into(), &[], self.theme.last_run_fg)
+                }
+            };
+            let (text, indices, truncated) = truncate_text(&text, *w, spec.truncate, indices);
+            let text_spans = self.highlight_text(&text, &indices, truncated, fg);
+            let text_w = console::measure_text_width(&text);
+            spans.extend(align_spans(text_spans, text_w, *w, spec.align));
             spans.push(" ".into());
         }
 
@@ -300,3 +1534,630 @@ impl Tui {
         ListItem::new(line).style(style)
     }
 }
+
+// `x` is relative to the area, and clamped so that the cursor stays in it
+fn cursor_position(area: Rect, x: usize) -> (u16, u16) {
+    let x = x.min(area.width.saturating_sub(1) as usize) as u16;
+    (area.x + x, area.y)
+}
+
+// at least the input and a row of the list are needed
+fn is_too_small(area: Rect) -> bool {
+    area.height < 2 || (area.width as usize) < layout::MIN_WIDTH
+}
+
+// truncates `text` to the width `w` on the side of `truncate`,
+// and moves the char indices of `indices` to the truncated text.
+// the flag is whether the text ends with an added ellipsis.
+fn truncate_text<'a>(
+    text: &'a str,
+    w: usize,
+    truncate: Truncate,
+    indices: &[usize],
+) -> (Cow<'a, str>, Vec<usize>, bool) {
+    if console::measure_text_width(text) <= w {
+        return (text.into(), indices.to_vec(), false);
+    }
+    match truncate {
+        Truncate::Right => (truncate_str(text, w, ELLIPSIS), indices.to_vec(), true),
+        Truncate::Left => {
+            let tail_w = w.saturating_sub(console::measure_text_width(ELLIPSIS));
+            let mut start = text.len();
+            let mut width = 0;
+            for (i, g) in text.grapheme_indices(true).rev() {
+                width += console::measure_text_width(g);
+                if width > tail_w {
+                    break;
+                }
+                start = i;
+            }
+            let removed = text[..start].chars().count();
+            let shift = ELLIPSIS.chars().count();
+            let indices = indices
+                .iter()
+                .filter(|i| **i >= removed)
+                .map(|i| i - removed + shift)
+                .collect();
+            (
+                format!("{ELLIPSIS}{}", &text[start..]).into(),
+                indices,
+                false,
+            )
+        }
+    }
+}
+
+// pads the spans of a text of the width `text_w` to `w`
+fn align_spans(spans: Vec<Span<'_>>, text_w: usize, w: usize, align: Align) -> Vec<Span<'_>> {
+    let (left, right) = split_padding(w.saturating_sub(text_w), align);
+    let mut ret = Vec::with_capacity(spans.len() + 2);
+    if left > 0 {
+        ret.push(" ".repeat(left).into());
+    }
+    ret.extend(spans);
+    if right > 0 {
+        ret.push(" ".repeat(right).into());
+    }
+    ret
+}
+
+// pads `text` with spaces to the display width `w`
+fn pad_text(text: &str, w: usize, align: Align) -> String {
+    let pad = w.saturating_sub(console::measure_text_width(text));
+    let (left, right) = split_padding(pad, align);
+    format!("{}{text}{}", " ".repeat(left), " ".repeat(right))
+}
+
+// the numbers of spaces put on the left and the right
+fn split_padding(pad: usize, align: Align) -> (usize, usize) {
+    match align {
+        Align::Left => (0, pad),
+        Align::Right => (pad, 0),
+        Align::Center => (pad / 2, pad - pad / 2),
+    }
+}
+
+// splits `text` into runs of matched and not matched grapheme clusters.
+// `matched_indices` are char indices, and a cluster is matched if any of its chars is.
+// the trailing ellipsis of a `truncated` text is never matched.
+fn highlight_spans(text: &str, matched_indices: &[usize], truncated: bool) -> Vec<(String, bool)> {
+    let body_len = match text.strip_suffix(ELLIPSIS) {
+        Some(body) if truncated => body.len(),
+        _ => text.len(),
+    };
+    let mut spans: Vec<(String, bool)> = Vec::new();
+    let mut char_index = 0;
+    for (pos, g) in text.grapheme_indices(true) {
+        let n = g.chars().count();
+        let matched =
+            pos < body_len && (char_index..char_index + n).any(|i| matched_indices.contains(&i));
+        match spans.last_mut() {
+            Some((s, m)) if *m == matched => s.push_str(g),
+            _ => spans.push((g.to_string(), matched)),
+        }
+        char_index += n;
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+    use rstest::rstest;
+
+    use crate::TargetKind;
+
+    use super::*;
+
+    fn target(name: &str, path: &str) -> Target {
+        Target::fixture(name, TargetKind::Bin, path)
+    }
+
+    fn tui(targets: Vec<Target>) -> Tui {
+        let mut tui = Tui::new(
+            targets,
+            Action::all(vec![]),
+            vec![cargo::DEV_PROFILE.into()],
+            WorkspaceState::default(),
+            MatchType::Substring,
+            Case::Smart,
+            &ColumnsConfig::default(),
+            ColorTheme::default(),
+        );
+        tui.list_height = 10;
+        tui
+    }
+
+    fn render(tui: &Tui, width: u16, height: u16) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| tui.render(f)).unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    // the column where `s` starts in the row `y`
+    fn find_x(buf: &Buffer, y: u16, s: &str) -> Option<u16> {
+        let symbols: Vec<&str> = (0..buf.area.width).map(|x| buf[(x, y)].symbol()).collect();
+        (0..symbols.len())
+            .find(|&x| {
+                symbols[x..]
+                    .iter()
+                    .zip(s.chars())
+                    .all(|(sym, c)| *sym == c.to_string())
+            })
+            .map(|x| x as u16)
+    }
+
+    #[rstest]
+    #[case("hello", &[], false, vec![("hello", false)])]
+    #[case("hello", &[1, 2], false, vec![("h", false), ("el", true), ("lo", false)])]
+    #[case("日本語", &[1], false, vec![("日", false), ("本", true), ("語", false)])]
+    #[case("cafe\u{301}", &[3], false, vec![("caf", false), ("e\u{301}", true)])]
+    #[case("cafe\u{301}", &[4], false, vec![("caf", false), ("e\u{301}", true)])]
+    #[case("abcd..", &[2, 3, 4, 5], true, vec![("ab", false), ("cd", true), ("..", false)])]
+    #[case("abcd..", &[2, 3, 4, 5], false, vec![("ab", false), ("cd..", true)])]
+    fn test_highlight_spans(
+        #[case] text: &str,
+        #[case] matched_indices: &[usize],
+        #[case] truncated: bool,
+        #[case] expected: Vec<(&str, bool)>,
+    ) {
+        let expected: Vec<(String, bool)> =
+            expected.into_iter().map(|(s, m)| (s.into(), m)).collect();
+        assert_eq!(highlight_spans(text, matched_indices, truncated), expected);
+    }
+
+    #[test]
+    fn test_render_list_non_ascii_alignment() {
+        let tui = tui(vec![
+            target("main", "src/main.rs"),
+            target("日本語", "src/bin/ja.rs"),
+            target("cafe\u{301}", "src/bin/cafe.rs"),
+            target("中文中文中文中文中文中文中文中文", "src/bin/zh.rs"),
+        ]);
+        let buf = render(&tui, 80, 6);
+        let xs: Vec<Option<u16>> = (1..5).map(|y| find_x(&buf, y, "src/")).collect();
+        assert!(xs[0].is_some());
+        assert!(xs.iter().all(|x| *x == xs[0]), "{xs:?}");
+    }
+
+    #[test]
+    fn test_render_list_highlight_combining_mark() {
+        let mut tui = tui(vec![target("cafe\u{301}_app", "src/main.rs")]);
+        tui.set_query("cafe");
+        let buf = render(&tui, 80, 2);
+        let x = find_x(&buf, 1, "caf").unwrap();
+        let matched_fg = tui.theme.name_match_fg;
+        for i in 0..3 {
+            assert_eq!(buf[(x + i, 1)].fg, matched_fg);
+        }
+        assert_eq!(buf[(x + 3, 1)].symbol(), "e\u{301}");
+        assert_eq!(buf[(x + 3, 1)].fg, matched_fg);
+        assert_ne!(buf[(x + 4, 1)].fg, matched_fg);
+    }
+
+    fn row(buf: &Buffer, y: u16) -> String {
+        let s: String = (0..buf.area.width).map(|x| buf[(x, y)].symbol()).collect();
+        s.trim_end().to_string()
+    }
+
+    fn targets() -> Vec<Target> {
+        let mut with_features = target("feat", "examples/feat.rs");
+        with_features.required_features = vec!["a".into(), "b".into()];
+        vec![
+            target("main", "src/main.rs"),
+            target("日本語", "src/bin/ja.rs"),
+            with_features,
+        ]
+    }
+
+    fn history_entry() -> HistoryEntry {
+        HistoryEntry {
+            target: "bin:pkg:main".into(),
+            action: "run".into(),
+            test: None,
+            features: FeatureSelection::default(),
+            profile: cargo::DEV_PROFILE.into(),
+            args: vec!["--verbose".into()],
+            additional_args: vec![],
+            timestamp: 0,
+            status: Some(1),
+        }
+    }
+
+    #[test]
+    fn test_lib_action() {
+        let mut lib = target("pkg", "src/lib.rs");
+        lib.kind = TargetKind::Lib;
+        let mut tui = tui(vec![lib]);
+        let buf = render(&tui, 80, 3);
+        assert!(row(&buf, 0).starts_with("  test "), "{}", row(&buf, 0));
+        assert!(matches!(tui.selected(), Ret::Selected(_, Action::Test, _)));
+
+        tui.next_action();
+        assert!(matches!(tui.selected(), Ret::Selected(_, Action::Bench, _)));
+    }
+
+    #[test]
+    fn test_history_keeps_order() {
+        let mut older = history_entry();
+        older.target = "pkg/bin/main".into();
+        let mut newer = history_entry();
+        newer.target = "pkg/bin/run_main".into();
+        let mut tui = tui(targets());
+        tui.enter_history(vec![newer, older]);
+        tui.set_query("main");
+        let indices: Vec<usize> = tui.filtered.iter().map(|ft| ft.index).collect();
+        assert_eq!(indices, vec![0, 1]);
+        assert!(tui.filtered[0].score < tui.filtered[1].score);
+    }
+
+    #[test]
+    fn test_render_any_size() {
+        let mut tuis = Vec::new();
+        tuis.push(tui(targets()));
+        let mut t = tui(targets());
+        t.enter_args(target("main", "src/main.rs"));
+        tuis.push(t);
+        let mut t = tui(targets());
+        t.enter_features(target("main", "src/main.rs"));
+        tuis.push(t);
+        let mut t = tui(targets());
+        t.enter_history(vec![history_entry()]);
+        tuis.push(t);
+        for position in [PreviewPosition::Right, PreviewPosition::Bottom] {
+            let mut t = tui(targets());
+            t.set_preview(&PreviewConfig {
+                show: true,
+                position,
+            });
+            tuis.push(t);
+        }
+
+        for tui in &mut tuis {
+            for _ in 0..2 {
+                tui.select_next();
+            }
+            for w in [0, 1, 2, 5, 11, 12, 19, 20, 30, 41, 42, 60, 77, 100, 200] {
+                for h in [0, 1, 2, 3, 5, 30] {
+                    tui.list_height = Tui::calc_list_height(h);
+                    render(tui, w, h);
+                }
+            }
+        }
+    }
+
+    #[rstest]
+    #[case(11, 10)]
+    #[case(80, 1)]
+    fn test_render_too_small(#[case] w: u16, #[case] h: u16) {
+        let tui = tui(targets());
+        let buf = render(&tui, w, h);
+        let expected: String = " terminal too small".chars().take(w as usize).collect();
+        assert_eq!(row(&buf, 0), expected);
+    }
+
+    #[rstest]
+    #[case(100, true, true, true)]
+    #[case(60, true, true, true)]
+    #[case(41, true, true, false)]
+    #[case(30, true, false, false)]
+    #[case(19, false, false, false)]
+    #[case(12, false, false, false)]
+    fn test_render_list_columns(
+        #[case] w: u16,
+        #[case] kind: bool,
+        #[case] path: bool,
+        #[case] features: bool,
+    ) {
+        let tui = tui(targets());
+        let buf = render(&tui, w, 4);
+        let row = row(&buf, 3);
+        assert!(
+            row.starts_with(if kind { " bin     feat" } else { " feat" }),
+            "{row}"
+        );
+        assert_eq!(row.contains("feat.rs"), path, "{row}");
+        assert_eq!(row.contains("--feat"), features, "{row}");
+    }
+
+    #[rstest]
+    #[case(80, true, true, true)]
+    #[case(40, false, true, true)]
+    #[case(32, false, false, true)]
+    #[case(20, false, false, false)]
+    fn test_render_input_badges(
+        #[case] w: u16,
+        #[case] match_type: bool,
+        #[case] nums: bool,
+        #[case] profile: bool,
+    ) {
+        let tui = tui(targets());
+        let buf = render(&tui, w, 4);
+        let row = row(&buf, 0);
+        assert_eq!(row.contains("substring"), match_type, "{row}");
+        assert_eq!(row.contains("(1 / 3)"), nums, "{row}");
+        assert_eq!(row.contains(cargo::DEV_PROFILE), profile, "{row}");
+    }
+
+    #[rstest]
+    #[case("src/bin/server.rs", 20, Truncate::Left, &[8, 9], "src/bin/server.rs", vec![8, 9])]
+    #[case("src/bin/server.rs", 12, Truncate::Left, &[8, 9], "../server.rs", vec![3, 4])]
+    #[case("src/bin/server.rs", 12, Truncate::Left, &[0, 1, 8], "../server.rs", vec![3])]
+    #[case("src/bin/server.rs", 12, Truncate::Right, &[8, 9], "src/bin/se..", vec![8, 9])]
+    #[case("日本語/main.rs", 10, Truncate::Left, &[4], "../main.rs", vec![3])]
+    #[case("日本語/main.rs", 11, Truncate::Left, &[3], "../main.rs", vec![2])]
+    #[case("cafe\u{301}/x.rs", 8, Truncate::Left, &[], "..e\u{301}/x.rs", vec![])]
+    fn test_truncate_text(
+        #[case] text: &str,
+        #[case] w: usize,
+        #[case] truncate: Truncate,
+        #[case] indices: &[usize],
+        #[case] expected_text: &str,
+        #[case] expected_indices: Vec<usize>,
+    ) {
+        let (actual_text, actual_indices, truncated) = truncate_text(text, w, truncate, indices);
+        assert_eq!(actual_text, expected_text);
+        assert_eq!(actual_indices, expected_indices);
+        assert_eq!(
+            truncated,
+            matches!(truncate, Truncate::Right) && text != expected_text
+        );
+    }
+
+    #[rstest]
+    #[case(Align::Left, "ab   ")]
+    #[case(Align::Right, "   ab")]
+    #[case(Align::Center, " ab  ")]
+    fn test_align_spans(#[case] align: Align, #[case] expected: &str) {
+        let spans = align_spans(vec!["a".into(), "b".into()], 2, 5, align);
+        let actual: String = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case("ab", Align::Left, "ab   ")]
+    #[case("日本", Align::Left, "日本 ")]
+    #[case("日本", Align::Center, "日本 ")]
+    #[case("日", Align::Center, " 日  ")]
+    #[case("日本語", Align::Right, "日本語")]
+    fn test_pad_text(#[case] text: &str, #[case] align: Align, #[case] expected: &str) {
+        assert_eq!(pad_text(text, 5, align), expected);
+    }
+
+    #[rstest]
+    #[case("日本")]
+    #[case("🦀🦀")]
+    fn test_render_input_wide_query(#[case] query: &str) {
+        let match_type_x = |query: &str| {
+            let mut tui = tui(vec![target("main", "src/main.rs")]);
+            tui.set_query(query);
+            find_x(&render(&tui, 80, 3), 0, "substring")
+        };
+        assert!(match_type_x("abcd").is_some());
+        assert_eq!(match_type_x(query), match_type_x("abcd"));
+    }
+
+    #[test]
+    fn test_render_list_custom_columns() {
+        let columns: ColumnsConfig = toml::from_str(
+            r#"
+            show = ["last_run", "package", "name"]
+            package = { width = 6, align = "right" }
+            "#,
+        )
+        .unwrap();
+        let mut targets = targets();
+        targets[0].package = "server".into();
+        let mut tui = Tui::new(
+            targets,
+            Action::all(vec![]),
+            vec![cargo::DEV_PROFILE.into()],
+            WorkspaceState::default(),
+            MatchType::Substring,
+            Case::Smart,
+            &columns,
+            ColorTheme::default(),
+        );
+        tui.list_height = 10;
+        let now = history::now();
+        tui.set_last_runs(HashMap::from([("server/bin/main".into(), now - 120)]));
+        let buf = render(&tui, 40, 4);
+        assert_eq!(row(&buf, 1), "   2m server main");
+        assert_eq!(row(&buf, 2), "         pkg 日 本 語");
+    }
+
+    fn workspace_targets() -> Vec<Target> {
+        let mut targets = vec![
+            target("server", "src/main.rs"),
+            target("client", "src/main.rs"),
+            target("server", "src/bin/server.rs"),
+        ];
+        targets[0].package = "api".into();
+        targets[1].package = "cli".into();
+        targets[2].package = "web".into();
+        targets
+    }
+
+    #[test]
+    fn test_render_list_package_column() {
+        let buf = render(&tui(workspace_targets()), 80, 4);
+        assert!(row(&buf, 1).starts_with(" bin     server                    api "));
+        assert!(row(&buf, 3).starts_with(" bin     server                    web "));
+
+        let buf = render(&tui(targets()), 80, 4);
+        assert!(!row(&buf, 1).contains("pkg"));
+    }
+
+    #[test]
+    fn test_grouped_rows() {
+        let mut targets = workspace_targets();
+        targets[1].package = "api".into();
+        let mut tui = tui(targets);
+        tui.set_grouped(true);
+        let header = |package: &str, len: usize, collapsed: bool| Row::Header {
+            package: package.into(),
+            len,
+            collapsed,
+        };
+        assert_eq!(
+            tui.rows,
+            vec![
+                header("api", 2, false),
+                Row::Item(0),
+                Row::Item(1),
+                header("web", 1, false),
+                Row::Item(2),
+            ]
+        );
+        assert_eq!(tui.cursor, 1);
+        assert_eq!(tui.selectable_position(), (1, 3));
+
+        // headers are skipped
+        tui.select_next();
+        tui.select_next();
+        assert_eq!(tui.cursor, 4);
+        assert_eq!(tui.selectable_position(), (3, 3));
+        tui.select_next();
+        assert_eq!(tui.cursor, 4);
+
+        // collapsed, then the header is selected
+        tui.select_prev();
+        tui.toggle_current_group();
+        assert_eq!(
+            tui.rows,
+            vec![
+                header("api", 2, true),
+                header("web", 1, false),
+                Row::Item(2)
+            ]
+        );
+        assert_eq!(tui.cursor, 0);
+        assert_eq!(tui.current_header(), Some("api"));
+        assert_eq!(tui.selectable_position(), (1, 2));
+
+        tui.toggle_current_group();
+        assert_eq!(tui.cursor, 1);
+        assert_eq!(tui.get_current_target().unwrap().package, "api");
+
+        tui.set_query("cli");
+        assert_eq!(tui.rows, vec![header("api", 1, false), Row::Item(0)]);
+        assert_eq!(tui.cursor, 1);
+    }
+
+    #[test]
+    fn test_set_grouped_keeps_cursor() {
+        let mut tui = tui(workspace_targets());
+        tui.select_next();
+        tui.select_next();
+        tui.set_grouped(true);
+        assert_eq!(tui.cursor, 5);
+        assert_eq!(tui.get_current_target().unwrap().package, "web");
+        tui.set_grouped(false);
+        assert_eq!(tui.cursor, 2);
+        assert_eq!(tui.get_current_target().unwrap().package, "web");
+    }
+
+    #[test]
+    fn test_render_grouped_list() {
+        let mut tui = tui(workspace_targets());
+        tui.set_grouped(true);
+        tui.toggle_current_group();
+        let buf = render(&tui, 80, 6);
+        assert!(row(&buf, 0).ends_with("(1 / 3)"), "{}", row(&buf, 0));
+        assert_eq!(row(&buf, 1), " + api (1)");
+        assert_eq!(row(&buf, 2), " - cli (1)");
+        assert!(row(&buf, 3).starts_with(" bin     client "));
+        assert!(!row(&buf, 3).contains("cli "));
+    }
+
+    #[test]
+    fn test_frecency_boost() {
+        let mut tui = tui(vec![
+            target("run_main", "src/bin/run_main.rs"),
+            target("main", "src/main.rs"),
+        ]);
+        let names = |tui: &Tui| -> Vec<String> {
+            tui.filtered
+                .iter()
+                .map(|ft| tui.targets[ft.index].name.clone())
+                .collect()
+        };
+        tui.set_query("main");
+        assert_eq!(names(&tui), vec!["main", "run_main"]);
+
+        tui.set_frecency(HashMap::from([("pkg/bin/run_main".into(), 80.0)]));
+        assert_eq!(names(&tui), vec!["run_main", "main"]);
+    }
+
+    fn preview_tui(position: PreviewPosition, src_path: &str) -> Tui {
+        let mut t = target("main", "src/main.rs");
+        t.src_path = src_path.into();
+        t.package_description = Some("A selector".into());
+        t.required_features = vec!["a".into(), "b".into()];
+        let mut tui = tui(vec![t]);
+        tui.set_preview(&PreviewConfig {
+            show: true,
+            position,
+        });
+        tui
+    }
+
+    #[rstest]
+    #[case(PreviewPosition::Right, 80, 6, vec![1, 2, 4])]
+    #[case(PreviewPosition::Bottom, 80, 11, vec![7, 8, 10])]
+    fn test_render_preview(
+        #[case] position: PreviewPosition,
+        #[case] w: u16,
+        #[case] h: u16,
+        #[case] ys: Vec<u16>,
+    ) {
+        let path = std::env::temp_dir().join(format!(
+            "cargo-selector-preview-{}-{position:?}.rs",
+            std::process::id()
+        ));
+        std::fs::write(&path, "use std::env;\n\nfn main() {\n}\n").unwrap();
+        let mut tui = preview_tui(position, path.to_str().unwrap());
+        tui.update_preview();
+        std::fs::remove_file(&path).unwrap();
+
+        let buf = render(&tui, w, h);
+        assert!(
+            row(&buf, ys[0]).ends_with("A selector"),
+            "{}",
+            row(&buf, ys[0])
+        );
+        assert!(row(&buf, ys[1]).ends_with("features: a, b"));
+        assert!(row(&buf, ys[2]).ends_with(" 3 fn main() {"));
+    }
+
+    #[test]
+    fn test_update_preview_in_tests_stage() {
+        let mut tui = preview_tui(PreviewPosition::Right, "/path/to/main.rs");
+        tui.targets.push(target("other", "src/bin/other.rs"));
+        tui.update_filter();
+        tui.update_preview();
+        tui.stage = Stage::Tests(Box::new(TestsStage {
+            target: tui.targets[0].clone(),
+            tests: Ok(vec!["a".into(), "b".into()]),
+            prev_input: Input::default(),
+        }));
+        tui.update_filter();
+        tui.select_next();
+        tui.update_preview();
+        let (path, _) = tui.preview.as_ref().unwrap();
+        assert_eq!(path, "/path/to/main.rs");
+    }
+
+    #[rstest]
+    #[case(PreviewPosition::Right, 39, 10)]
+    #[case(PreviewPosition::Bottom, 80, 6)]
+    fn test_render_preview_not_fit(
+        #[case] position: PreviewPosition,
+        #[case] w: u16,
+        #[case] h: u16,
+    ) {
+        let tui = preview_tui(position, "src/main.rs");
+        let buf = render(&tui, w, h);
+        assert!((0..h).all(|y| !row(&buf, y).contains("A selector")));
+    }
+}
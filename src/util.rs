@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 pub fn digits(n: usize) -> usize {
     if n == 0 {
         return 1;
@@ -10,3 +12,128 @@ pub fn digits(n: usize) -> usize {
     }
     c
 }
+
+// Splits `s` into words following the quoting rules of the POSIX shell:
+// - characters enclosed in single quotes are preserved literally
+// - in double quotes, a backslash escapes only `$`, `` ` ``, `"`, `\` and newline
+// - outside quotes, a backslash escapes any character
+// Variable expansions and other shell features are not supported.
+pub fn split_words(s: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let w = word.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => w.push(c),
+                        None => return Err("unterminated single quote".into()),
+                    }
+                }
+            }
+            '"' => {
+                let w = word.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('\n') => {}
+                            Some(c @ ('$' | '`' | '"' | '\\')) => w.push(c),
+                            Some(c) => {
+                                w.push('\\');
+                                w.push(c);
+                            }
+                            None => return Err("unterminated double quote".into()),
+                        },
+                        Some(c) => w.push(c),
+                        None => return Err("unterminated double quote".into()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some(c) => word.get_or_insert_default().push(c),
+                None => return Err("trailing backslash".into()),
+            },
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+// Quotes `s` so that the POSIX shell reads it back as a single word.
+// Double quotes are preferred for readability, unless `s` contains characters that are special in them.
+pub fn quote_word(s: &str) -> Cow<'_, str> {
+    let is_plain = |c: char| c.is_alphanumeric() || "-_./:=@%+,".contains(c);
+    if !s.is_empty() && !s.starts_with('=') && s.chars().all(is_plain) {
+        return s.into();
+    }
+    if !s.contains(['"', '$', '`', '\\', '!']) {
+        return format!("\"{s}\"").into();
+    }
+    format!("'{}'", s.replace('\'', "'\\''")).into()
+}
+
+pub fn join_words<S: AsRef<str>>(words: &[S]) -> String {
+    words
+        .iter()
+        .map(|w| quote_word(w.as_ref()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("cargo run --bin xyz", vec!["cargo", "run", "--bin", "xyz"])]
+    #[case("  cargo   run  ", vec!["cargo", "run"])]
+    #[case("cargo watch -x \"run --bin xyz\"", vec!["cargo", "watch", "-x", "run --bin xyz"])]
+    #[case("echo 'a \"b\" c' d''e \"\"", vec!["echo", "a \"b\" c", "de", ""])]
+    #[case("-- --name \"Hello World\"", vec!["--", "--name", "Hello World"])]
+    #[case("a\\ b c\\\\d \\'e\\'", vec!["a b", "c\\d", "'e'"])]
+    #[case("\"a\\\"b\\\\c\\$d\\e\"", vec!["a\"b\\c$d\\e"])]
+    #[case("'a\\b'", vec!["a\\b"])]
+    #[case("a\\\nb \"c\\\nd\"", vec!["ab", "cd"])]
+    #[case("", vec![])]
+    fn test_split_words(#[case] s: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(
+            split_words(s),
+            Ok(expected.into_iter().map(String::from).collect())
+        );
+    }
+
+    #[rstest]
+    #[case("echo 'abc", "unterminated single quote")]
+    #[case("echo \"abc", "unterminated double quote")]
+    #[case("echo \"abc\\", "unterminated double quote")]
+    #[case("echo abc\\", "trailing backslash")]
+    fn test_split_words_error(#[case] s: &str, #[case] expected: &str) {
+        assert_eq!(split_words(s), Err(expected.to_string()));
+    }
+
+    #[rstest]
+    #[case("--bin", "--bin")]
+    #[case("src/main.rs", "src/main.rs")]
+    #[case("", "\"\"")]
+    #[case("Hello World", "\"Hello World\"")]
+    #[case("it's", "\"it's\"")]
+    #[case("$HOME", "'$HOME'")]
+    #[case("say \"hi\"", "'say \"hi\"'")]
+    #[case("it's \"ok\"", "'it'\\''s \"ok\"'")]
+    #[case("a\\b", "'a\\b'")]
+    #[case("=cmd", "\"=cmd\"")]
+    #[case("*.rs", "\"*.rs\"")]
+    fn test_quote_word(#[case] s: &str, #[case] expected: &str) {
+        assert_eq!(quote_word(s), expected);
+        assert_eq!(split_words(&quote_word(s)), Ok(vec![s.to_string()]));
+    }
+}